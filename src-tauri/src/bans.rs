@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::sources::Candidate;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BannedPhoto {
+    pub source: String,
+    pub id: String,
+}
+
+/// Photographers are matched by name within one provider, since the same name
+/// on two sites is not necessarily the same person.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BannedPhotographer {
+    pub provider: String,
+    pub author: String,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BanList {
+    #[serde(default)]
+    pub photos: Vec<BannedPhoto>,
+    #[serde(default)]
+    pub photographers: Vec<BannedPhotographer>,
+}
+
+pub fn bans_path(app: &AppHandle) -> PathBuf {
+    app.path().app_data_dir().unwrap().join("bans.json")
+}
+
+impl BanList {
+    pub fn load(app: &AppHandle) -> Self {
+        fs::read_to_string(bans_path(app))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let path = bans_path(app);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+
+    pub fn is_banned(&self, candidate: &Candidate) -> bool {
+        self.photos
+            .iter()
+            .any(|p| p.source == candidate.source && p.id == candidate.id)
+            || self.photographers.iter().any(|p| {
+                p.provider == candidate.attribution.provider
+                    && p.author.eq_ignore_ascii_case(&candidate.attribution.author)
+            })
+    }
+}
+
+fn update(app: &AppHandle, change: impl FnOnce(&mut BanList)) -> Result<BanList, String> {
+    let mut bans = BanList::load(app);
+    change(&mut bans);
+    bans.save(app)?;
+    Ok(bans)
+}
+
+pub fn ban_candidate(
+    app: &AppHandle,
+    candidate: &Candidate,
+    photographer: bool,
+) -> Result<BanList, String> {
+    if photographer {
+        ban_photographer(
+            app.clone(),
+            candidate.attribution.provider.clone(),
+            candidate.attribution.author.clone(),
+        )
+    } else {
+        ban_photo(app.clone(), candidate.source.clone(), candidate.id.clone())
+    }
+}
+
+// Bans the wallpaper on screen and moves on to another one.
+pub async fn ban_current_and_rotate(app: &AppHandle, photographer: bool) -> Result<bool, String> {
+    let (entry, _) = crate::history::current_entry(app)
+        .ok_or_else(|| "No wallpaper has been applied yet".to_string())?;
+    ban_candidate(app, &entry.candidate, photographer)?;
+    crate::scheduler::rotate(app, false).await
+}
+
+#[tauri::command]
+pub fn get_ban_list(app: AppHandle) -> BanList {
+    BanList::load(&app)
+}
+
+#[tauri::command]
+pub fn ban_photo(app: AppHandle, source: String, id: String) -> Result<BanList, String> {
+    update(&app, |bans| {
+        let photo = BannedPhoto { source, id };
+        if !bans.photos.contains(&photo) {
+            bans.photos.push(photo);
+        }
+    })
+}
+
+#[tauri::command]
+pub fn unban_photo(app: AppHandle, source: String, id: String) -> Result<BanList, String> {
+    update(&app, |bans| {
+        bans.photos.retain(|p| !(p.source == source && p.id == id))
+    })
+}
+
+#[tauri::command]
+pub fn ban_photographer(
+    app: AppHandle,
+    provider: String,
+    author: String,
+) -> Result<BanList, String> {
+    update(&app, |bans| {
+        let photographer = BannedPhotographer { provider, author };
+        if !bans.photographers.contains(&photographer) {
+            bans.photographers.push(photographer);
+        }
+    })
+}
+
+#[tauri::command]
+pub fn unban_photographer(
+    app: AppHandle,
+    provider: String,
+    author: String,
+) -> Result<BanList, String> {
+    update(&app, |bans| {
+        bans.photographers
+            .retain(|p| !(p.provider == provider && p.author.eq_ignore_ascii_case(&author)))
+    })
+}
+
+#[tauri::command]
+pub async fn ban_current_wallpaper(app: AppHandle, photographer: bool) -> Result<bool, String> {
+    ban_current_and_rotate(&app, photographer).await
+}
@@ -0,0 +1,124 @@
+use image::imageops::FilterType;
+use tauri::AppHandle;
+
+use crate::sources::Candidate;
+use crate::wallpaper::DownloadLimits;
+
+// Bits that may differ between two hashes of the same picture after
+// re-encoding, resizing or light recompression.
+const SIMILAR_DISTANCE: u32 = 6;
+
+/// 64-bit difference hash: the image is shrunk to 9×8 greyscale and each bit
+/// records whether a pixel is brighter than its right-hand neighbour.
+pub fn perceptual_hash(image_bytes: &[u8], limits: DownloadLimits) -> Result<u64, String> {
+    let small = crate::wallpaper::decode_image(image_bytes, limits)?
+        .resize_exact(9, 8, FilterType::Triangle)
+        .to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    Ok(hash)
+}
+
+pub fn format_hash(hash: u64) -> String {
+    format!("{hash:016x}")
+}
+
+/// Ids and image hashes of the last few wallpapers, newest first.
+pub struct RecentlyShown {
+    ids: Vec<(String, String)>,
+    hashes: Vec<u64>,
+}
+
+impl RecentlyShown {
+    pub fn load(app: &AppHandle, window: usize) -> Self {
+        let entries = crate::history::recent_entries(app, window);
+        RecentlyShown {
+            ids: entries
+                .iter()
+                .map(|e| (e.candidate.source.clone(), e.candidate.id.clone()))
+                .collect(),
+            hashes: entries
+                .iter()
+                .filter_map(|e| e.hash.as_deref())
+                .filter_map(|h| u64::from_str_radix(h, 16).ok())
+                .collect(),
+        }
+    }
+
+    pub fn contains(&self, candidate: &Candidate) -> bool {
+        self.age(candidate).is_some()
+    }
+
+    /// How many wallpapers were shown since the candidate: 0 for the one on
+    /// screen, None if it is not among the recent ones.
+    pub fn age(&self, candidate: &Candidate) -> Option<usize> {
+        self.ids
+            .iter()
+            .position(|(source, id)| source == &candidate.source && id == &candidate.id)
+    }
+
+    /// Whether the candidate is the wallpaper on screen right now.
+    pub fn is_current(&self, candidate: &Candidate) -> bool {
+        self.age(candidate) == Some(0)
+    }
+
+    pub fn has_similar(&self, hash: u64) -> bool {
+        self.hashes
+            .iter()
+            .any(|h| (h ^ hash).count_ones() <= SIMILAR_DISTANCE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shown(ids: &[&str]) -> RecentlyShown {
+        RecentlyShown {
+            ids: ids
+                .iter()
+                .map(|id| ("local".to_string(), id.to_string()))
+                .collect(),
+            hashes: vec![0xff00_ff00_ff00_ff00],
+        }
+    }
+
+    fn candidate(id: &str) -> Candidate {
+        serde_json::from_value(serde_json::json!({
+            "source": "local",
+            "id": id,
+            "width": 0,
+            "height": 0,
+            "previewUrl": id,
+            "downloadUrl": id,
+            "attribution": { "author": id, "provider": "Local folder" }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn ages_count_from_the_wallpaper_on_screen() {
+        let recent = shown(&["c", "b", "a"]);
+        assert_eq!(recent.age(&candidate("c")), Some(0));
+        assert_eq!(recent.age(&candidate("a")), Some(2));
+        assert_eq!(recent.age(&candidate("d")), None);
+        assert!(recent.is_current(&candidate("c")));
+        assert!(!recent.is_current(&candidate("b")));
+        assert!(recent.contains(&candidate("b")));
+    }
+
+    #[test]
+    fn near_identical_hashes_are_similar() {
+        let recent = shown(&[]);
+        assert!(recent.has_similar(0xff00_ff00_ff00_ff3f));
+        assert!(!recent.has_similar(0x00ff_00ff_00ff_00ff));
+    }
+}
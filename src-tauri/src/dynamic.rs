@@ -0,0 +1,435 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tauri::{AppHandle, Manager};
+
+use crate::schedule::CronSpec;
+use crate::settings::DynamicSetOptions;
+use crate::solar::SolarPhase;
+
+const MANIFEST_FILE: &str = "manifest.json";
+// A solar phase recurs within a handful of transitions, polar regions aside.
+const MAX_PHASE_STEPS: usize = 6;
+
+/// One image of a set and the moment it takes over: a local "HH:MM" time or the
+/// start of a solar phase.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Frame {
+    pub file: String,
+    #[serde(default)]
+    pub time: Option<String>,
+    #[serde(default)]
+    pub phase: Option<SolarPhase>,
+}
+
+/// `manifest.json` in an imported folder, next to the frame images.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub name: String,
+    pub frames: Vec<Frame>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WallpaperSet {
+    pub id: String,
+    pub name: String,
+    pub frames: Vec<Frame>,
+}
+
+fn sets_dir(app: &AppHandle) -> PathBuf {
+    app.path().app_data_dir().unwrap().join("sets")
+}
+
+fn read_manifest(dir: &Path) -> Result<Manifest, String> {
+    let data = fs::read_to_string(dir.join(MANIFEST_FILE))
+        .map_err(|e| format!("Cannot read {MANIFEST_FILE}: {e}"))?;
+    let manifest: Manifest =
+        serde_json::from_str(&data).map_err(|e| format!("Invalid {MANIFEST_FILE}: {e}"))?;
+    if manifest.frames.is_empty() {
+        return Err("The set has no frames".to_string());
+    }
+    for frame in &manifest.frames {
+        match (&frame.time, &frame.phase) {
+            (Some(time), None) => {
+                time_spec(time)?;
+            }
+            (None, Some(_)) => {}
+            _ => {
+                return Err(format!(
+                    "Frame {} needs exactly one of time or phase",
+                    frame.file
+                ))
+            }
+        }
+        // Frames must stay inside the set folder.
+        if !crate::settings::is_plain_file_name(&frame.file) {
+            return Err(format!(
+                "Frame {} must be a file in the set folder",
+                frame.file
+            ));
+        }
+    }
+    Ok(manifest)
+}
+
+fn time_spec(time: &str) -> Result<CronSpec, String> {
+    let (hour, minute) = time
+        .trim()
+        .split_once(':')
+        .and_then(|(h, m)| Some((h.parse::<u32>().ok()?, m.parse::<u32>().ok()?)))
+        .filter(|(h, m)| *h < 24 && *m < 60)
+        .ok_or_else(|| format!("Invalid time '{time}', expected HH:MM"))?;
+    CronSpec::parse(&format!("{minute} {hour} * * *"))
+}
+
+fn set_dir(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    if !crate::settings::is_plain_file_name(id) {
+        return Err(format!("Unknown wallpaper set {id}"));
+    }
+    Ok(sets_dir(app).join(id))
+}
+
+fn load_set(app: &AppHandle, id: &str) -> Result<(PathBuf, Manifest), String> {
+    let dir = set_dir(app, id)?;
+    let manifest = read_manifest(&dir).map_err(|e| format!("Wallpaper set {id}: {e}"))?;
+    Ok((dir, manifest))
+}
+
+enum Trigger {
+    Time(CronSpec),
+    Phase(SolarPhase),
+}
+
+/// When each frame of a set takes over.
+pub struct FrameTimes {
+    triggers: Vec<Trigger>,
+    location: Option<(f64, f64)>,
+}
+
+impl FrameTimes {
+    pub fn load(app: &AppHandle, options: &DynamicSetOptions) -> Result<Self, String> {
+        let (_, manifest) = load_set(app, &options.set_id)?;
+        Self::new(&manifest, options)
+    }
+
+    fn new(manifest: &Manifest, options: &DynamicSetOptions) -> Result<Self, String> {
+        let triggers: Vec<Trigger> = manifest
+            .frames
+            .iter()
+            .map(|frame| match (&frame.time, frame.phase) {
+                (Some(time), _) => time_spec(time).map(Trigger::Time),
+                (None, Some(phase)) => Ok(Trigger::Phase(phase)),
+                (None, None) => Err(format!("Frame {} has no time or phase", frame.file)),
+            })
+            .collect::<Result<_, _>>()?;
+        let location = options.latitude.zip(options.longitude);
+        if location.is_none() && triggers.iter().any(|t| matches!(t, Trigger::Phase(_))) {
+            return Err("This set follows the sun; set a latitude and longitude".to_string());
+        }
+        Ok(FrameTimes { triggers, location })
+    }
+
+    fn next_occurrence(&self, index: usize, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        match &self.triggers[index] {
+            Trigger::Time(spec) => spec.next_after(&after),
+            Trigger::Phase(wanted) => {
+                let (latitude, longitude) = self.location?;
+                let mut cursor = after.to_utc();
+                for _ in 0..MAX_PHASE_STEPS {
+                    let (at, phase) =
+                        crate::solar::next_transition(cursor, latitude, longitude, true)?;
+                    if phase == *wanted {
+                        return Some(at.with_timezone(&Local));
+                    }
+                    cursor = at;
+                }
+                None
+            }
+        }
+    }
+
+    /// The next time any frame takes over.
+    pub fn next_change(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        (0..self.triggers.len())
+            .filter_map(|i| self.next_occurrence(i, after))
+            .min()
+    }
+
+    /// Frames repeat daily, so the one that took over most recently is the one
+    /// whose next turn is furthest away.
+    pub fn current_frame(&self, at: DateTime<Local>) -> Option<usize> {
+        (0..self.triggers.len())
+            .filter_map(|i| self.next_occurrence(i, at).map(|t| (i, t)))
+            .max_by_key(|(_, t)| *t)
+            .map(|(i, _)| i)
+    }
+}
+
+fn frame_stem(index: usize) -> String {
+    format!("frame{index}")
+}
+
+// Crops every frame for the current monitor layout and presentation, unless that
+// was done before.
+async fn prepare(
+    app: &AppHandle,
+    dir: &Path,
+    manifest: &Manifest,
+    screens: &[crate::screens::ScreenInfo],
+) -> Result<PathBuf, String> {
+    let presentation =
+        crate::wallpaper::Presentation::from_settings(&crate::settings::load_settings_inner(app));
+    let cache = dir
+        .join("cache")
+        .join(crate::wallpaper::layout_key(screens))
+        .join(presentation.cache_key());
+    let done_marker = cache.join(".complete");
+    if done_marker.exists() {
+        return Ok(cache);
+    }
+    for (index, frame) in manifest.frames.iter().enumerate() {
+        let bytes = fs::read(dir.join(&frame.file))
+            .map_err(|e| format!("Cannot read frame {}: {e}", frame.file))?;
+        crate::wallpaper::precrop(
+            app,
+            screens,
+            bytes,
+            &cache,
+            &frame_stem(index),
+            presentation,
+        )
+        .await
+        .map_err(|e| format!("Cannot prepare frame {}: {e}", frame.file))?;
+    }
+    fs::write(&done_marker, "").map_err(|e| e.to_string())?;
+    Ok(cache)
+}
+
+/// Shows the frame that should be on screen now.
+pub async fn show_current_frame(
+    app: &AppHandle,
+    options: &DynamicSetOptions,
+) -> Result<bool, String> {
+    let times = FrameTimes::load(app, options)?;
+    let index = times
+        .current_frame(Local::now())
+        .ok_or_else(|| "No frame of this set is ever shown".to_string())?;
+    let (dir, manifest) = load_set(app, &options.set_id)?;
+    let screens = crate::wallpaper::detect_screens(app)?;
+    let cache = prepare(app, &dir, &manifest, &screens).await?;
+    crate::wallpaper::apply_precropped(app, &screens, &cache, &frame_stem(index)).await
+}
+
+fn list_sets(app: &AppHandle) -> Vec<WallpaperSet> {
+    let Ok(entries) = fs::read_dir(sets_dir(app)) else {
+        return Vec::new();
+    };
+    let mut sets: Vec<WallpaperSet> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry.file_name().to_string_lossy().to_string();
+            let manifest = read_manifest(&entry.path()).ok()?;
+            Some(WallpaperSet {
+                id,
+                name: manifest.name,
+                frames: manifest.frames,
+            })
+        })
+        .collect();
+    sets.sort_by(|a, b| a.name.cmp(&b.name));
+    sets
+}
+
+fn copy_set(source: &Path, dir: &Path, manifest: &Manifest) -> Result<(), String> {
+    fs::copy(source.join(MANIFEST_FILE), dir.join(MANIFEST_FILE)).map_err(|e| e.to_string())?;
+    for frame in &manifest.frames {
+        fs::copy(source.join(&frame.file), dir.join(&frame.file))
+            .map_err(|e| format!("Cannot copy frame {}: {e}", frame.file))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_wallpaper_sets(app: AppHandle) -> Vec<WallpaperSet> {
+    list_sets(&app)
+}
+
+/// Copies a folder holding `manifest.json` and its frames into the app's data
+/// and crops every frame for the current screens.
+#[tauri::command]
+pub async fn import_wallpaper_set(app: AppHandle, folder: String) -> Result<WallpaperSet, String> {
+    let source = PathBuf::from(&folder);
+    let manifest = read_manifest(&source)?;
+
+    let slug: String = manifest
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let id = format!("{slug}-{}", Local::now().timestamp_millis());
+    let dir = sets_dir(&app).join(&id);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let imported = match copy_set(&source, &dir, &manifest) {
+        Ok(()) => match crate::wallpaper::detect_screens(&app) {
+            Ok(screens) => prepare(&app, &dir, &manifest, &screens).await.map(|_| ()),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+    // Leave nothing half-imported behind.
+    if let Err(e) = imported {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+
+    Ok(WallpaperSet {
+        id,
+        name: manifest.name,
+        frames: manifest.frames,
+    })
+}
+
+#[tauri::command]
+pub fn delete_wallpaper_set(app: AppHandle, id: String) -> Result<Vec<WallpaperSet>, String> {
+    let dir = set_dir(&app, &id)?;
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+    }
+    Ok(list_sets(&app))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn set_folder(name: &str, manifest: serde_json::Value) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wallcraft-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(MANIFEST_FILE), manifest.to_string()).unwrap();
+        dir
+    }
+
+    fn manifest_error(name: &str, frames: serde_json::Value) -> String {
+        let dir = set_folder(
+            name,
+            serde_json::json!({ "name": "Test", "frames": frames }),
+        );
+        let result = read_manifest(&dir);
+        let _ = fs::remove_dir_all(&dir);
+        result.unwrap_err()
+    }
+
+    fn options(latitude: Option<f64>, longitude: Option<f64>) -> DynamicSetOptions {
+        DynamicSetOptions {
+            set_id: "test".to_string(),
+            latitude,
+            longitude,
+        }
+    }
+
+    fn frames(frames: serde_json::Value) -> Manifest {
+        serde_json::from_value(serde_json::json!({ "name": "Test", "frames": frames })).unwrap()
+    }
+
+    fn local(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2026, 6, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn frames_need_exactly_one_trigger() {
+        let both = manifest_error(
+            "set-both",
+            serde_json::json!([{ "file": "a.jpg", "time": "07:00", "phase": "day" }]),
+        );
+        assert!(both.contains("exactly one of time or phase"), "{both}");
+        let neither = manifest_error("set-neither", serde_json::json!([{ "file": "a.jpg" }]));
+        assert!(
+            neither.contains("exactly one of time or phase"),
+            "{neither}"
+        );
+        let bad_time = manifest_error(
+            "set-bad-time",
+            serde_json::json!([{ "file": "a.jpg", "time": "25:00" }]),
+        );
+        assert!(bad_time.contains("expected HH:MM"), "{bad_time}");
+
+        let dir = set_folder(
+            "set-ok",
+            serde_json::json!({
+                "name": "Test",
+                "frames": [
+                    { "file": "a.jpg", "time": "07:00" },
+                    { "file": "b.jpg", "phase": "dusk" }
+                ]
+            }),
+        );
+        assert_eq!(read_manifest(&dir).unwrap().frames.len(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn frames_stay_in_the_set_folder() {
+        for file in ["../secret.jpg", "sub/a.jpg", "/etc/passwd"] {
+            let error = manifest_error(
+                "set-escape",
+                serde_json::json!([{ "file": file, "time": "07:00" }]),
+            );
+            assert!(
+                error.contains("must be a file in the set folder"),
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn solar_frames_need_a_location() {
+        let manifest = frames(serde_json::json!([
+            { "file": "a.jpg", "phase": "day" },
+            { "file": "b.jpg", "phase": "night" }
+        ]));
+        let error = FrameTimes::new(&manifest, &options(Some(51.5), None))
+            .err()
+            .unwrap();
+        assert!(error.contains("latitude and longitude"), "{error}");
+        assert!(FrameTimes::new(&manifest, &options(Some(51.5), Some(-0.1))).is_ok());
+
+        // Sets that only go by the clock do not care where they are.
+        let timed = frames(serde_json::json!([{ "file": "a.jpg", "time": "07:00" }]));
+        assert!(FrameTimes::new(&timed, &options(None, None)).is_ok());
+    }
+
+    #[test]
+    fn the_evening_frame_lasts_past_midnight() {
+        let manifest = frames(serde_json::json!([
+            { "file": "morning.jpg", "time": "06:00" },
+            { "file": "evening.jpg", "time": "22:00" }
+        ]));
+        let times = FrameTimes::new(&manifest, &options(None, None)).unwrap();
+
+        assert_eq!(times.current_frame(local(10, 12, 0)), Some(0));
+        assert_eq!(times.current_frame(local(10, 23, 0)), Some(1));
+        assert_eq!(times.current_frame(local(11, 1, 0)), Some(1));
+        assert_eq!(times.current_frame(local(11, 5, 59)), Some(1));
+        assert_eq!(times.current_frame(local(11, 6, 0)), Some(0));
+
+        assert_eq!(times.next_change(local(10, 23, 0)), Some(local(11, 6, 0)));
+        assert_eq!(times.next_change(local(11, 6, 0)), Some(local(11, 22, 0)));
+    }
+}
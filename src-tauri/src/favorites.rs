@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::sources::Candidate;
+
+/// A starred wallpaper. Its image is copied out of the history cache so it
+/// survives history eviction.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Favorite {
+    pub candidate: Candidate,
+    /// RFC 3339 timestamp in local time.
+    pub added_at: String,
+    /// Relative to the favorites directory.
+    pub file: String,
+}
+
+pub fn favorites_dir(app: &AppHandle) -> PathBuf {
+    app.path().app_data_dir().unwrap().join("favorites")
+}
+
+pub fn favorites_path(app: &AppHandle) -> PathBuf {
+    favorites_dir(app).join("favorites.json")
+}
+
+pub fn load(app: &AppHandle) -> Vec<Favorite> {
+    fs::read_to_string(favorites_path(app))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(app: &AppHandle, favorites: &[Favorite]) -> Result<(), String> {
+    let dir = favorites_dir(app);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(favorites).map_err(|e| e.to_string())?;
+    fs::write(favorites_path(app), json).map_err(|e| e.to_string())
+}
+
+pub fn favorite_current(app: &AppHandle) -> Result<Favorite, String> {
+    let (entry, cached) = crate::history::current_entry(app)
+        .ok_or_else(|| "No wallpaper has been applied yet".to_string())?;
+    let mut favorites = load(app);
+    if let Some(existing) = favorites.iter().find(|f| {
+        f.candidate.source == entry.candidate.source && f.candidate.id == entry.candidate.id
+    }) {
+        return Ok(existing.clone());
+    }
+
+    let dir = favorites_dir(app);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let file = entry.cache_file.clone();
+    fs::copy(&cached, dir.join(&file)).map_err(|e| format!("Cached image is missing: {e}"))?;
+
+    let favorite = Favorite {
+        candidate: entry.candidate,
+        added_at: chrono::Local::now().to_rfc3339(),
+        file,
+    };
+    favorites.push(favorite.clone());
+    save(app, &favorites)?;
+    Ok(favorite)
+}
+
+#[tauri::command]
+pub fn get_favorites(app: AppHandle) -> Vec<Favorite> {
+    load(&app)
+}
+
+#[tauri::command]
+pub fn favorite_current_wallpaper(app: AppHandle) -> Result<Favorite, String> {
+    favorite_current(&app)
+}
+
+#[tauri::command]
+pub fn remove_favorite(
+    app: AppHandle,
+    source: String,
+    id: String,
+) -> Result<Vec<Favorite>, String> {
+    let mut favorites = load(&app);
+    let dir = favorites_dir(&app);
+    favorites.retain(|f| {
+        let matches = f.candidate.source == source && f.candidate.id == id;
+        if matches {
+            let _ = fs::remove_file(dir.join(&f.file));
+        }
+        !matches
+    });
+    save(&app, &favorites)?;
+    Ok(favorites)
+}
@@ -0,0 +1,296 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::screens::ScreenInfo;
+use crate::sources::Candidate;
+use crate::wallpaper::{Applied, Crop};
+
+// Each entry keeps the original image bytes on disk, so the list is capped by
+// length and the cached images by their total size.
+const HISTORY_LIMIT: usize = 100;
+const HISTORY_CACHE_BYTES: u64 = 500 * 1024 * 1024;
+
+/// One wallpaper change, with everything needed to show it again.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    /// Source key, photo id, download URL and attribution.
+    pub candidate: Candidate,
+    pub crops: Vec<Crop>,
+    pub screens: Vec<ScreenInfo>,
+    /// RFC 3339 timestamp in local time.
+    pub applied_at: String,
+    /// Original image bytes, relative to the history directory.
+    pub cache_file: String,
+    /// Perceptual hash of the image, used to spot re-uploads under another id.
+    #[serde(default)]
+    pub hash: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct History {
+    entries: Vec<HistoryEntry>,
+    // Index of the entry currently on screen; moves with previous/next.
+    #[serde(default)]
+    position: Option<usize>,
+}
+
+fn history_dir(app: &AppHandle) -> PathBuf {
+    app.path().app_data_dir().unwrap().join("history")
+}
+
+impl History {
+    fn load(app: &AppHandle) -> Self {
+        fs::read_to_string(history_dir(app).join("history.json"))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let dir = history_dir(app);
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        crate::settings::write_atomic(&dir.join("history.json"), json.as_bytes())
+            .map_err(|e| e.to_string())
+    }
+
+    // Drops the oldest entries until both limits hold, deleting cached images no
+    // remaining entry shares. The newest entry is always kept.
+    fn evict(&mut self, dir: &Path, max_entries: usize, max_bytes: u64) {
+        let mut sizes: HashMap<String, u64> = HashMap::new();
+        for entry in &self.entries {
+            sizes.entry(entry.cache_file.clone()).or_insert_with(|| {
+                fs::metadata(dir.join(&entry.cache_file))
+                    .map(|m| m.len())
+                    .unwrap_or(0)
+            });
+        }
+        let mut total: u64 = sizes.values().sum();
+        while self.entries.len() > 1 && (self.entries.len() > max_entries || total > max_bytes) {
+            let evicted = self.entries.remove(0);
+            if !self
+                .entries
+                .iter()
+                .any(|e| e.cache_file == evicted.cache_file)
+            {
+                total -= sizes.remove(&evicted.cache_file).unwrap_or(0);
+                let _ = fs::remove_file(dir.join(&evicted.cache_file));
+            }
+        }
+    }
+
+    fn current(&self) -> Option<usize> {
+        self.position
+            .filter(|&p| p < self.entries.len())
+            .or_else(|| self.entries.len().checked_sub(1))
+    }
+}
+
+fn store(
+    app: &AppHandle,
+    candidate: &Candidate,
+    image_bytes: &[u8],
+    applied: &Applied,
+    hash: Option<u64>,
+) -> Result<(), String> {
+    let mut history = History::load(app);
+    let dir = history_dir(app);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    // The same photo shown again shares its cached file.
+    let cache_file = match history
+        .entries
+        .iter()
+        .find(|e| e.candidate.source == candidate.source && e.candidate.id == candidate.id)
+    {
+        Some(existing) if dir.join(&existing.cache_file).exists() => existing.cache_file.clone(),
+        _ => {
+            let name = format!("{}.img", chrono::Local::now().timestamp_millis());
+            fs::write(dir.join(&name), image_bytes).map_err(|e| e.to_string())?;
+            name
+        }
+    };
+
+    history.entries.push(HistoryEntry {
+        candidate: candidate.clone(),
+        crops: applied.crops.clone(),
+        screens: applied.screens.clone(),
+        applied_at: chrono::Local::now().to_rfc3339(),
+        cache_file,
+        hash: hash.map(crate::dedupe::format_hash),
+    });
+    history.evict(&dir, HISTORY_LIMIT, HISTORY_CACHE_BYTES);
+    history.position = Some(history.entries.len() - 1);
+    history.save(app)
+}
+
+// Called by the apply pipeline after a new wallpaper is on screen. History is a
+// convenience, so failing to write it never fails the wallpaper change.
+pub fn record(
+    app: &AppHandle,
+    candidate: &Candidate,
+    image_bytes: &[u8],
+    applied: &Applied,
+    hash: Option<u64>,
+) {
+    if let Err(e) = store(app, candidate, image_bytes, applied, hash) {
+        eprintln!("[WallCraft] failed to record history: {e}");
+    }
+}
+
+async fn step(app: &AppHandle, forward: bool) -> Result<bool, String> {
+    let mut history = History::load(app);
+    let current = history
+        .current()
+        .ok_or_else(|| "No wallpaper history yet".to_string())?;
+    let target = if forward {
+        current + 1
+    } else {
+        current
+            .checked_sub(1)
+            .ok_or_else(|| "Already at the oldest wallpaper".to_string())?
+    };
+    // Stepping forward past the newest entry fetches a brand new wallpaper.
+    if target >= history.entries.len() {
+        return crate::scheduler::rotate(app, false).await;
+    }
+
+    let entry = history.entries[target].clone();
+    let image_bytes = fs::read(history_dir(app).join(&entry.cache_file))
+        .map_err(|e| format!("Cached image is missing: {e}"))?;
+    let applied = crate::wallpaper::apply_cached(app, &image_bytes).await?;
+    if applied.succeeded() > 0 {
+        history.position = Some(target);
+        history.save(app)?;
+        let _ = app.emit("next-wallpaper", &entry.candidate);
+    }
+    Ok(applied.complete())
+}
+
+/// Up to `count` of the most recently applied entries, newest first.
+pub fn recent_entries(app: &AppHandle, count: usize) -> Vec<HistoryEntry> {
+    let mut entries = History::load(app).entries;
+    entries.reverse();
+    entries.truncate(count);
+    entries
+}
+
+/// The entry currently on screen and the path of its cached image.
+pub fn current_entry(app: &AppHandle) -> Option<(HistoryEntry, PathBuf)> {
+    let history = History::load(app);
+    let entry = history.entries.get(history.current()?)?.clone();
+    let path = history_dir(app).join(&entry.cache_file);
+    Some((entry, path))
+}
+
+pub async fn step_and_report(app: &AppHandle, forward: bool) {
+    if let Err(e) = step(app, forward).await {
+        let _ = app.emit("scheduler-error", e);
+    }
+}
+
+#[tauri::command]
+pub async fn previous_wallpaper(app: AppHandle) -> Result<bool, String> {
+    step(&app, false).await
+}
+
+#[tauri::command]
+pub async fn next_wallpaper(app: AppHandle) -> Result<bool, String> {
+    step(&app, true).await
+}
+
+/// Newest first.
+#[tauri::command]
+pub fn get_history(app: AppHandle) -> Vec<HistoryEntry> {
+    let mut entries = History::load(&app).entries;
+    entries.reverse();
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, cache_file: &str) -> HistoryEntry {
+        serde_json::from_value(serde_json::json!({
+            "candidate": {
+                "source": "unsplash",
+                "id": id,
+                "width": 1920,
+                "height": 1080,
+                "previewUrl": "https://example.com/preview.jpg",
+                "downloadUrl": "https://example.com/full.jpg",
+                "attribution": { "author": "Someone", "provider": "Unsplash" }
+            },
+            "crops": [],
+            "screens": [],
+            "appliedAt": "2024-03-12T08:00:00+00:00",
+            "cacheFile": cache_file
+        }))
+        .unwrap()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wallcraft-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn evicts_oldest_entries_beyond_the_byte_budget() {
+        let dir = temp_dir("history-bytes");
+        for name in ["a.img", "b.img", "c.img"] {
+            fs::write(dir.join(name), vec![0u8; 100]).unwrap();
+        }
+        let mut history = History {
+            // "a" was shown twice, so its image stays while either entry does.
+            entries: vec![
+                entry("a", "a.img"),
+                entry("b", "b.img"),
+                entry("a", "a.img"),
+                entry("c", "c.img"),
+            ],
+            position: None,
+        };
+
+        history.evict(&dir, 100, 250);
+        let ids: Vec<&str> = history
+            .entries
+            .iter()
+            .map(|e| e.candidate.id.as_str())
+            .collect();
+        assert_eq!(ids, ["a", "c"]);
+        assert!(dir.join("a.img").exists());
+        assert!(!dir.join("b.img").exists());
+
+        // The newest entry survives even when it alone is over budget.
+        history.evict(&dir, 100, 50);
+        assert_eq!(history.entries.len(), 1);
+        assert!(!dir.join("a.img").exists());
+        assert!(dir.join("c.img").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn evicts_beyond_the_entry_limit() {
+        let dir = temp_dir("history-count");
+        let mut history = History {
+            entries: (0..5)
+                .map(|i| entry(&i.to_string(), &format!("{i}.img")))
+                .collect(),
+            position: None,
+        };
+        history.evict(&dir, 3, u64::MAX);
+        assert_eq!(history.entries[0].candidate.id, "2");
+        assert_eq!(history.entries.len(), 3);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
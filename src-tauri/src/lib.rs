@@ -1,18 +1,100 @@
+mod bans;
+mod bundle;
+mod dedupe;
+mod dynamic;
+mod favorites;
+mod history;
+mod http;
+mod playlist;
+mod profiles;
+mod schedule;
 mod screens;
+mod secrets;
 mod settings;
+mod settings_watch;
+mod solar;
+mod sources;
 mod wallpaper;
 mod scheduler;
+mod unsplash;
 
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tauri::{
-    Manager,
-    menu::{Menu, MenuItem},
+    Emitter, Manager,
+    menu::{CheckMenuItem, Menu, MenuItem, Submenu},
     tray::{MouseButton, TrayIconBuilder, TrayIconEvent},
-    Emitter,
 };
 
 pub struct AppState {
     pub scheduler_abort: Mutex<Option<tokio::task::AbortHandle>>,
+    pub unsplash_quota: Arc<Mutex<unsplash::RateLimit>>,
+    pub folder_library: Arc<sources::local::FolderLibrary>,
+    pub playlist: playlist::PlaylistState,
+    pub settings: Mutex<Option<settings::Settings>>,
+    pub settings_watcher: Mutex<Option<notify::RecommendedWatcher>>,
+    pub secrets: secrets::SecretStore,
+}
+
+const TRAY_ID: &str = "main";
+const PROFILE_ITEM_PREFIX: &str = "profile:";
+
+// Builds the tray menu, including one entry per profile. Called again whenever
+// the profiles change.
+pub fn refresh_tray_menu(app: &tauri::AppHandle) -> tauri::Result<()> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    let settings = settings::load_settings_inner(app);
+    let mut names: Vec<String> = settings.profiles.iter().map(|p| p.name.clone()).collect();
+    if names.is_empty() {
+        names.push(settings.active_profile.clone());
+    }
+    let profile_items = names
+        .iter()
+        .map(|name| {
+            CheckMenuItem::with_id(
+                app,
+                format!("{PROFILE_ITEM_PREFIX}{name}"),
+                name,
+                true,
+                name.eq_ignore_ascii_case(&settings.active_profile),
+                None::<&str>,
+            )
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let profile_refs: Vec<&dyn tauri::menu::IsMenuItem<_>> = profile_items
+        .iter()
+        .map(|item| item as &dyn tauri::menu::IsMenuItem<_>)
+        .collect();
+    let profiles_i = Submenu::with_items(app, "Profiles", true, &profile_refs)?;
+
+    let open_i = MenuItem::with_id(app, "open", "Open WallCraft", true, None::<&str>)?;
+    let next_i = MenuItem::with_id(app, "next", "Next Wallpaper", true, None::<&str>)?;
+    let prev_i = MenuItem::with_id(app, "previous", "Previous Wallpaper", true, None::<&str>)?;
+    let favorite_i = MenuItem::with_id(
+        app,
+        "favorite",
+        "Favorite Current Wallpaper",
+        true,
+        None::<&str>,
+    )?;
+    let ban_i = MenuItem::with_id(app, "ban", "Ban Current Wallpaper", true, None::<&str>)?;
+    let sep = tauri::menu::PredefinedMenuItem::separator(app)?;
+    let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &open_i,
+            &next_i,
+            &prev_i,
+            &favorite_i,
+            &ban_i,
+            &profiles_i,
+            &sep,
+            &quit_i,
+        ],
+    )?;
+    tray.set_menu(Some(menu))
 }
 
 pub fn run() {
@@ -20,17 +102,24 @@ pub fn run() {
         .setup(|app| {
             app.manage(AppState {
                 scheduler_abort: Mutex::new(None),
+                unsplash_quota: Arc::new(Mutex::new(unsplash::RateLimit::default())),
+                folder_library: Arc::new(sources::local::FolderLibrary::default()),
+                playlist: playlist::PlaylistState::default(),
+                settings: Mutex::new(None),
+                settings_watcher: Mutex::new(None),
+                secrets: secrets::SecretStore::default(),
             });
 
-            // System tray
-            let open_i = MenuItem::with_id(app, "open", "Open WallCraft", true, None::<&str>)?;
-            let next_i = MenuItem::with_id(app, "next", "Next Wallpaper", true, None::<&str>)?;
-            let sep = tauri::menu::PredefinedMenuItem::separator(app)?;
-            let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&open_i, &next_i, &sep, &quit_i])?;
+            // Pick up hand edits to settings.json without a restart
+            match settings_watch::watch(app.handle()) {
+                Ok(watcher) => {
+                    *app.state::<AppState>().settings_watcher.lock().unwrap() = Some(watcher)
+                }
+                Err(e) => eprintln!("[WallCraft] failed to watch settings: {e}"),
+            }
 
-            TrayIconBuilder::new()
-                .menu(&menu)
+            // System tray; the menu is filled in below
+            TrayIconBuilder::with_id(TRAY_ID)
                 .tooltip("WallCraft")
                 .on_menu_event(|app, event| match event.id.as_ref() {
                     "open" => {
@@ -40,11 +129,41 @@ pub fn run() {
                         }
                     }
                     "next" => {
-                        let _ = app.emit("next-wallpaper", ());
+                        let app = app.clone();
+                        tauri::async_runtime::spawn(async move {
+                            scheduler::rotate_and_report(&app, false).await;
+                        });
+                    }
+                    "previous" => {
+                        let app = app.clone();
+                        tauri::async_runtime::spawn(async move {
+                            history::step_and_report(&app, false).await;
+                        });
+                    }
+                    "favorite" => {
+                        if let Err(e) = favorites::favorite_current(app) {
+                            let _ = app.emit("scheduler-error", e);
+                        }
+                    }
+                    "ban" => {
+                        let app = app.clone();
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = bans::ban_current_and_rotate(&app, false).await {
+                                let _ = app.emit("scheduler-error", e);
+                            }
+                        });
                     }
                     "quit" => {
                         app.exit(0);
                     }
+                    id if id.starts_with(PROFILE_ITEM_PREFIX) => {
+                        let name = &id[PROFILE_ITEM_PREFIX.len()..];
+                        if let Err(e) = profiles::switch(app, name) {
+                            let _ = app.emit("settings-invalid", e);
+                        }
+                        // Put the check mark back if the switch failed.
+                        let _ = refresh_tray_menu(app);
+                    }
                     _ => {}
                 })
                 .on_tray_icon_event(|tray, event| {
@@ -60,11 +179,12 @@ pub fn run() {
                     }
                 })
                 .build(app)?;
+            refresh_tray_menu(app.handle())?;
 
             // Start scheduler from saved settings
             let app_handle = app.handle().clone();
             let saved = settings::load_settings_inner(&app_handle);
-            if saved.update_interval != "manual" {
+            if saved.scheduling_enabled() {
                 let state = app.state::<AppState>();
                 scheduler::start_scheduler_inner(&state, saved, app_handle);
             }
@@ -79,6 +199,40 @@ pub fn run() {
             settings::save_settings,
             scheduler::start_scheduler_cmd,
             scheduler::stop_scheduler_cmd,
+            scheduler::rotate_now,
+            schedule::upcoming_fire_times,
+            solar::upcoming_solar_events,
+            dynamic::get_wallpaper_sets,
+            dynamic::import_wallpaper_set,
+            dynamic::delete_wallpaper_set,
+            profiles::get_profiles,
+            profiles::create_profile,
+            profiles::duplicate_profile,
+            profiles::switch_profile,
+            profiles::delete_profile,
+            bundle::export_bundle,
+            bundle::import_bundle,
+            secrets::get_secret_store_status,
+            secrets::unlock_secret_store,
+            history::previous_wallpaper,
+            history::next_wallpaper,
+            history::get_history,
+            favorites::get_favorites,
+            favorites::favorite_current_wallpaper,
+            favorites::remove_favorite,
+            bans::get_ban_list,
+            bans::ban_photo,
+            bans::unban_photo,
+            bans::ban_photographer,
+            bans::unban_photographer,
+            bans::ban_current_wallpaper,
+            unsplash::unsplash_verify_key,
+            unsplash::unsplash_topics,
+            unsplash::unsplash_topic_photos,
+            unsplash::unsplash_search_photos,
+            unsplash::unsplash_collections,
+            unsplash::unsplash_collection_photos,
+            unsplash::get_unsplash_rate_limit,
         ])
         .on_window_event(|window, event| {
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
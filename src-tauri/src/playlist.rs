@@ -0,0 +1,197 @@
+use rand::{distributions::WeightedIndex, prelude::Distribution, rngs::StdRng, SeedableRng};
+use std::sync::Mutex;
+
+use crate::settings::{PlaylistOptions, SourceConfig};
+
+/// Weighted pick with a cap on consecutive repeats. All randomness comes from
+/// the owned RNG, so a seeded picker replays the same sequence.
+pub struct Picker {
+    rng: StdRng,
+    last: Option<usize>,
+    streak: u32,
+}
+
+impl Picker {
+    pub fn new(seed: Option<u64>) -> Self {
+        Picker {
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            last: None,
+            streak: 0,
+        }
+    }
+
+    /// Index of the next entry, or None when every weight is zero.
+    pub fn pick(&mut self, weights: &[u32], max_in_a_row: u32) -> Option<usize> {
+        let mut allowed = weights.to_vec();
+        if let Some(last) = self.last.and_then(|last| allowed.get_mut(last)) {
+            if max_in_a_row > 0 && self.streak >= max_in_a_row {
+                *last = 0;
+            }
+        }
+        // A playlist with a single usable entry has nothing else to switch to.
+        if allowed.iter().all(|&w| w == 0) {
+            allowed = weights.to_vec();
+        }
+        let index = WeightedIndex::new(&allowed).ok()?.sample(&mut self.rng);
+
+        if self.last == Some(index) {
+            self.streak += 1;
+        } else {
+            self.last = Some(index);
+            self.streak = 1;
+        }
+        Some(index)
+    }
+}
+
+// Keeps the picker alive across rotations so the repeat rule and the seeded
+// sequence carry over. Editing the playlist starts a fresh picker.
+#[derive(Default)]
+pub struct PlaylistState {
+    current: Mutex<Option<(PlaylistOptions, Picker)>>,
+}
+
+impl PlaylistState {
+    pub fn next(&self, options: &PlaylistOptions) -> Result<SourceConfig, String> {
+        if options
+            .entries
+            .iter()
+            .any(|e| matches!(e.source, SourceConfig::Playlist(_)))
+        {
+            return Err("Playlists cannot contain other playlists".to_string());
+        }
+
+        let mut current = self.current.lock().unwrap();
+        if current.as_ref().map(|(o, _)| o) != Some(options) {
+            *current = Some((options.clone(), Picker::new(options.seed)));
+        }
+        let (_, picker) = current.as_mut().unwrap();
+
+        let weights: Vec<u32> = options.entries.iter().map(|e| e.weight).collect();
+        let index = picker
+            .pick(&weights, options.max_in_a_row)
+            .ok_or_else(|| "Playlist has no sources with a weight above zero".to_string())?;
+        Ok(options.entries[index].source.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{PlaylistEntry, RotationSource};
+
+    fn draws(picker: &mut Picker, weights: &[u32], max_in_a_row: u32, n: usize) -> Vec<usize> {
+        (0..n)
+            .map(|_| picker.pick(weights, max_in_a_row).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_sequence() {
+        let weights = [60, 30, 10];
+        let first = draws(&mut Picker::new(Some(42)), &weights, 2, 200);
+        let second = draws(&mut Picker::new(Some(42)), &weights, 2, 200);
+        let other = draws(&mut Picker::new(Some(43)), &weights, 2, 200);
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn respects_weights() {
+        let n = 20_000;
+        let picks = draws(&mut Picker::new(Some(7)), &[60, 30, 10], 0, n);
+        for (index, expected) in [(0, 0.6), (1, 0.3), (2, 0.1)] {
+            let share = picks.iter().filter(|&&p| p == index).count() as f64 / n as f64;
+            assert!(
+                (share - expected).abs() < 0.02,
+                "entry {index}: {share} instead of {expected}"
+            );
+        }
+        // A zero weight is never picked.
+        let picks = draws(&mut Picker::new(Some(7)), &[5, 0, 5], 0, 1000);
+        assert!(!picks.contains(&1));
+    }
+
+    #[test]
+    fn never_exceeds_max_in_a_row() {
+        for max_in_a_row in 1..=3 {
+            for seed in 0..20 {
+                let picks = draws(&mut Picker::new(Some(seed)), &[95, 5], max_in_a_row, 500);
+                let mut streak = 0;
+                let mut last = None;
+                for pick in picks {
+                    streak = if last == Some(pick) { streak + 1 } else { 1 };
+                    last = Some(pick);
+                    assert!(streak <= max_in_a_row, "seed {seed}: streak of {streak}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn single_usable_entry_repeats_and_zero_weights_pick_nothing() {
+        let mut picker = Picker::new(Some(1));
+        assert_eq!(draws(&mut picker, &[0, 3], 1, 5), [1, 1, 1, 1, 1]);
+        assert_eq!(picker.pick(&[0, 0], 2), None);
+        assert_eq!(picker.pick(&[], 2), None);
+    }
+
+    fn entry(weight: u32, slug: &str) -> PlaylistEntry {
+        PlaylistEntry {
+            weight,
+            source: SourceConfig::Unsplash {
+                rotation: RotationSource::Topic {
+                    slug: slug.to_string(),
+                },
+            },
+        }
+    }
+
+    fn options(seed: u64) -> PlaylistOptions {
+        PlaylistOptions {
+            entries: vec![entry(60, "nature"), entry(40, "architecture")],
+            max_in_a_row: 2,
+            seed: Some(seed),
+        }
+    }
+
+    #[test]
+    fn state_keeps_the_sequence_until_the_playlist_changes() {
+        let state = PlaylistState::default();
+        let first: Vec<SourceConfig> = (0..20).map(|_| state.next(&options(9)).unwrap()).collect();
+        // An unchanged playlist continues the sequence rather than restarting it.
+        let mut picker = Picker::new(Some(9));
+        let expected: Vec<SourceConfig> = draws(&mut picker, &[60, 40], 2, 20)
+            .into_iter()
+            .map(|i| options(9).entries[i].source.clone())
+            .collect();
+        assert_eq!(first, expected);
+
+        // Editing it starts over from the seed.
+        state.next(&options(10)).unwrap();
+        let restarted: Vec<SourceConfig> =
+            (0..20).map(|_| state.next(&options(9)).unwrap()).collect();
+        assert_eq!(restarted, first);
+    }
+
+    #[test]
+    fn rejects_nested_playlists_and_empty_weights() {
+        let state = PlaylistState::default();
+        let mut nested = options(1);
+        nested.entries.push(PlaylistEntry {
+            weight: 1,
+            source: SourceConfig::Playlist(options(2)),
+        });
+        assert_eq!(
+            state.next(&nested).unwrap_err(),
+            "Playlists cannot contain other playlists"
+        );
+
+        let mut empty = options(1);
+        empty.entries.iter_mut().for_each(|e| e.weight = 0);
+        assert!(state.next(&empty).is_err());
+    }
+}
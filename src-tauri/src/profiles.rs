@@ -0,0 +1,137 @@
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::settings::{FieldError, Profile, Settings, SettingsError};
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileList {
+    pub active: String,
+    pub profiles: Vec<Profile>,
+}
+
+fn list(mut settings: Settings) -> ProfileList {
+    settings.store_active_profile();
+    ProfileList {
+        active: settings.active_profile,
+        profiles: settings.profiles,
+    }
+}
+
+fn invalid(field: &str, message: String) -> SettingsError {
+    SettingsError::Invalid {
+        errors: vec![FieldError {
+            field: field.to_string(),
+            message,
+        }],
+    }
+}
+
+fn check_new_name(settings: &Settings, name: &str) -> Result<(), SettingsError> {
+    if name.trim().is_empty() {
+        return Err(invalid("name", "Profile name is required".to_string()));
+    }
+    if settings.find_profile(name).is_some() {
+        return Err(invalid(
+            "name",
+            format!("There is already a profile named {}", name.trim()),
+        ));
+    }
+    Ok(())
+}
+
+// Loads the current settings, applies `change` and saves the result.
+fn update(
+    app: &AppHandle,
+    change: impl FnOnce(&mut Settings) -> Result<(), SettingsError>,
+) -> Result<ProfileList, SettingsError> {
+    let mut settings = crate::settings::load_settings_inner(app);
+    settings.store_active_profile();
+    change(&mut settings)?;
+    crate::settings::store(app, &app.state::<crate::AppState>(), settings)?;
+    Ok(list(crate::settings::load_settings_inner(app)))
+}
+
+/// Makes the named profile active; its source and timing take effect at once,
+/// and its source, fit mode and effects replace the wallpaper on screen.
+pub fn switch(app: &AppHandle, name: &str) -> Result<ProfileList, SettingsError> {
+    let previous = crate::settings::load_settings_inner(app).active_profile;
+    let profiles = update(app, |settings| {
+        settings
+            .switch_profile(name)
+            .map_err(|e| invalid("name", e))
+    })?;
+    if !profiles.active.eq_ignore_ascii_case(&previous) {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            crate::scheduler::rotate_and_report(&app, false).await;
+        });
+    }
+    Ok(profiles)
+}
+
+#[tauri::command]
+pub fn get_profiles(app: AppHandle) -> ProfileList {
+    list(crate::settings::load_settings_inner(&app))
+}
+
+/// Adds a profile that rotates nothing until it is configured.
+#[tauri::command]
+pub fn create_profile(app: AppHandle, name: String) -> Result<ProfileList, SettingsError> {
+    update(&app, |settings| {
+        check_new_name(settings, &name)?;
+        settings.profiles.push(Profile::new(name.trim()));
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn duplicate_profile(
+    app: AppHandle,
+    name: String,
+    new_name: String,
+) -> Result<ProfileList, SettingsError> {
+    update(&app, |settings| {
+        let index = settings
+            .find_profile(&name)
+            .ok_or_else(|| invalid("name", format!("No profile named {name}")))?;
+        check_new_name(settings, &new_name)?;
+        let copy = Profile {
+            name: new_name.trim().to_string(),
+            ..settings.profiles[index].clone()
+        };
+        settings.profiles.push(copy);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn switch_profile(app: AppHandle, name: String) -> Result<ProfileList, SettingsError> {
+    switch(&app, &name)
+}
+
+/// Deleting the active profile switches to the first remaining one.
+#[tauri::command]
+pub fn delete_profile(app: AppHandle, name: String) -> Result<ProfileList, SettingsError> {
+    update(&app, |settings| {
+        let index = settings
+            .find_profile(&name)
+            .ok_or_else(|| invalid("name", format!("No profile named {name}")))?;
+        if settings.profiles.len() == 1 {
+            return Err(invalid(
+                "name",
+                "The last profile cannot be deleted".to_string(),
+            ));
+        }
+        if settings.find_profile(&settings.active_profile) == Some(index) {
+            let next = settings.profiles[if index == 0 { 1 } else { 0 }]
+                .name
+                .clone();
+            settings
+                .switch_profile(&next)
+                .map_err(|e| invalid("name", e))?;
+        }
+        settings.profiles.remove(index);
+        Ok(())
+    })
+}
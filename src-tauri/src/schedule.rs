@@ -0,0 +1,469 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration, Local, LocalResult, NaiveDate, TimeZone};
+
+use crate::settings::Schedule;
+
+// Far enough to find a Feb 29th or a "31st of a 30-day month that is also a
+// Friday"; anything rarer is treated as never firing.
+const SEARCH_DAYS: u32 = 366 * 8;
+
+/// Source of "now" for the scheduler. Next-fire calculations and waits only see
+/// the clock through this, so they can be driven by a fixed or stepped time.
+#[async_trait]
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Local>;
+    async fn sleep(&self, duration: std::time::Duration);
+}
+
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+
+    async fn sleep(&self, duration: std::time::Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+#[async_trait]
+impl<C: Clock + ?Sized> Clock for std::sync::Arc<C> {
+    fn now(&self) -> DateTime<Local> {
+        (**self).now()
+    }
+
+    async fn sleep(&self, duration: std::time::Duration) {
+        (**self).sleep(duration).await;
+    }
+}
+
+/// A clock that stands still until it is moved, for tests. Sleeping moves it
+/// on at once.
+#[cfg(test)]
+pub struct ManualClock(std::sync::Mutex<DateTime<Local>>);
+
+#[cfg(test)]
+impl ManualClock {
+    pub fn at(now: DateTime<Local>) -> Self {
+        ManualClock(std::sync::Mutex::new(now))
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.0.lock().unwrap() += by;
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Local> {
+        *self.0.lock().unwrap()
+    }
+
+    async fn sleep(&self, duration: std::time::Duration) {
+        self.advance(Duration::from_std(duration).unwrap());
+        // Let other tasks see the new time.
+        tokio::task::yield_now().await;
+    }
+}
+
+/// One parsed cron line. Each field is a bitmask of the allowed values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CronSpec {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    // Cron matches either day field when both are restricted, and only the
+    // restricted one otherwise.
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+const MONTH_NAMES: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+fn parse_value(token: &str, min: u32, names: &[&str]) -> Option<u32> {
+    if let Ok(n) = token.parse() {
+        return Some(n);
+    }
+    let lower = token.to_ascii_lowercase();
+    names
+        .iter()
+        .position(|n| *n == lower)
+        .map(|i| i as u32 + min)
+}
+
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("invalid step in '{item}'"))?;
+                (range, step)
+            }
+            None => (item, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            let a = parse_value(a, min, names).ok_or_else(|| format!("invalid value '{a}'"))?;
+            let b = parse_value(b, min, names).ok_or_else(|| format!("invalid value '{b}'"))?;
+            (a, b)
+        } else {
+            let v =
+                parse_value(range, min, names).ok_or_else(|| format!("invalid value '{range}'"))?;
+            // "5/15" means from 5 to the end of the range in steps of 15.
+            (v, if item.contains('/') { max } else { v })
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("'{item}' is outside {min}-{max}"));
+        }
+        for v in (start..=end).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+impl CronSpec {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expanded = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Invalid cron expression '{expression}': expected 5 fields"
+            ));
+        };
+        let invalid = |e: String| format!("Invalid cron expression '{expression}': {e}");
+
+        let mut weekdays = parse_field(weekday, 0, 7, WEEKDAY_NAMES).map_err(invalid)?;
+        // Both 0 and 7 mean Sunday.
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(CronSpec {
+            minutes: parse_field(minute, 0, 59, &[]).map_err(invalid)?,
+            hours: parse_field(hour, 0, 23, &[]).map_err(invalid)?,
+            days: parse_field(day, 1, 31, &[]).map_err(invalid)?,
+            months: parse_field(month, 1, 12, MONTH_NAMES).map_err(invalid)?,
+            weekdays,
+            days_restricted: !day.starts_with('*'),
+            weekdays_restricted: !weekday.starts_with('*'),
+        })
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            (true, false) => day,
+            (false, true) => weekday,
+            (false, false) => true,
+        }
+    }
+
+    /// First fire time strictly after `after`, in `after`'s time zone.
+    ///
+    /// Times are matched on the local wall clock. A time that occurs twice when
+    /// clocks go back fires only the first time; a time skipped when clocks go
+    /// forward fires an hour later, once the clock has jumped past it.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let tz = after.timezone();
+        let mut date = after.naive_local().date();
+        for _ in 0..SEARCH_DAYS {
+            if self.matches_date(date) {
+                for hour in (0..24).filter(|h| self.hours & (1 << h) != 0) {
+                    for minute in (0..60).filter(|m| self.minutes & (1 << m) != 0) {
+                        let local = date.and_hms_opt(hour, minute, 0)?;
+                        let fire = match tz.from_local_datetime(&local) {
+                            LocalResult::Single(t) => Some(t),
+                            // Not every zone implementation lists the earlier instant first.
+                            LocalResult::Ambiguous(a, b) => Some(a.min(b)),
+                            LocalResult::None => tz
+                                .from_local_datetime(&(local + Duration::hours(1)))
+                                .earliest(),
+                        };
+                        if let Some(fire) = fire.filter(|t| t > after) {
+                            return Some(fire);
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+}
+
+/// A schedule compiled into cron lines; it fires whenever any of them does.
+pub struct CompiledSchedule {
+    specs: Vec<CronSpec>,
+}
+
+impl CompiledSchedule {
+    pub fn compile(schedule: &Schedule) -> Result<Self, String> {
+        let specs = match schedule {
+            Schedule::Cron { expression } => vec![CronSpec::parse(expression)?],
+            Schedule::TimesOfDay { times, days } => {
+                if times.is_empty() {
+                    return Err("Add at least one time of day".to_string());
+                }
+                let days = if days.trim().is_empty() {
+                    "*"
+                } else {
+                    days.trim()
+                };
+                times
+                    .iter()
+                    .map(|time| {
+                        let (hour, minute) = time
+                            .trim()
+                            .split_once(':')
+                            .and_then(|(h, m)| {
+                                Some((h.parse::<u32>().ok()?, m.parse::<u32>().ok()?))
+                            })
+                            .filter(|(h, m)| *h < 24 && *m < 60)
+                            .ok_or_else(|| format!("Invalid time '{time}', expected HH:MM"))?;
+                        CronSpec::parse(&format!("{minute} {hour} * * {days}"))
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        Ok(CompiledSchedule { specs })
+    }
+
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        self.specs
+            .iter()
+            .filter_map(|spec| spec.next_after(after))
+            .min()
+    }
+
+    /// The next `count` fire times after the clock's current time.
+    pub fn upcoming(&self, clock: &dyn Clock, count: usize) -> Vec<DateTime<Local>> {
+        let mut times = Vec::with_capacity(count);
+        let mut cursor = clock.now();
+        while times.len() < count {
+            let Some(next) = self.next_after(&cursor) else {
+                break;
+            };
+            times.push(next);
+            cursor = next;
+        }
+        times
+    }
+}
+
+/// Upcoming fire times as RFC 3339 strings. Previews `schedule` when given,
+/// otherwise the saved one.
+#[tauri::command]
+pub fn upcoming_fire_times(
+    app: tauri::AppHandle,
+    schedule: Option<Schedule>,
+    count: Option<usize>,
+) -> Result<Vec<String>, String> {
+    let Some(schedule) = schedule.or_else(|| crate::settings::load_settings_inner(&app).schedule)
+    else {
+        return Ok(Vec::new());
+    };
+    Ok(CompiledSchedule::compile(&schedule)?
+        .upcoming(&SystemClock, count.unwrap_or(5))
+        .iter()
+        .map(|t| t.to_rfc3339())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, NaiveDateTime, Timelike};
+
+    /// Central European time with 2024's changes: clocks go forward at
+    /// 02:00 on 31 March and back at 03:00 on 27 October.
+    #[derive(Clone, Copy, Debug)]
+    struct Cet;
+
+    const HOUR: i32 = 3600;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, 0, 0)
+            .unwrap()
+    }
+
+    impl TimeZone for Cet {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Cet
+        }
+
+        fn offset_from_utc_datetime(&self, at: &NaiveDateTime) -> FixedOffset {
+            let summer = *at >= utc(2024, 3, 31, 1) && *at < utc(2024, 10, 27, 1);
+            FixedOffset::east_opt(if summer { 2 * HOUR } else { HOUR }).unwrap()
+        }
+
+        fn offset_from_utc_date(&self, date: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let valid: Vec<FixedOffset> = [HOUR, 2 * HOUR]
+                .into_iter()
+                .map(|secs| FixedOffset::east_opt(secs).unwrap())
+                .filter(|offset| {
+                    let at = *local - Duration::seconds(offset.local_minus_utc() as i64);
+                    self.offset_from_utc_datetime(&at) == *offset
+                })
+                .collect();
+            match valid[..] {
+                [] => LocalResult::None,
+                [offset] => LocalResult::Single(offset),
+                // Later instant first, as some zone implementations do.
+                [standard, summer] => LocalResult::Ambiguous(standard, summer),
+                _ => unreachable!(),
+            }
+        }
+
+        fn offset_from_local_date(&self, date: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+        }
+    }
+
+    fn cet(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Cet> {
+        Cet.with_ymd_and_hms(y, m, d, h, min, 0).earliest().unwrap()
+    }
+
+    fn spec(expression: &str) -> CronSpec {
+        CronSpec::parse(expression).unwrap()
+    }
+
+    #[test]
+    fn ambiguous_time_fires_once_at_the_earlier_instant() {
+        let spec = spec("30 2 * * *");
+        let first = spec.next_after(&cet(2024, 10, 27, 0, 0)).unwrap();
+        assert_eq!(first.to_rfc3339(), "2024-10-27T02:30:00+02:00");
+        // The repeat of 02:30 an hour later, in winter time, is skipped.
+        let second = spec.next_after(&first).unwrap();
+        assert_eq!(second.to_rfc3339(), "2024-10-28T02:30:00+01:00");
+    }
+
+    #[test]
+    fn skipped_time_fires_an_hour_later() {
+        let spec = spec("30 2 * * *");
+        let fire = spec.next_after(&cet(2024, 3, 31, 0, 0)).unwrap();
+        assert_eq!(fire.to_rfc3339(), "2024-03-31T03:30:00+02:00");
+        let next = spec.next_after(&fire).unwrap();
+        assert_eq!(next.to_rfc3339(), "2024-04-01T02:30:00+02:00");
+    }
+
+    #[test]
+    fn hourly_schedule_across_the_change_back() {
+        let spec = spec("0 * * * *");
+        let mut at = cet(2024, 10, 27, 1, 30);
+        let mut fires = Vec::new();
+        for _ in 0..3 {
+            at = spec.next_after(&at).unwrap();
+            fires.push(at.to_rfc3339());
+        }
+        // 02:00 happens twice but fires once; 03:00 follows it.
+        assert_eq!(
+            fires,
+            [
+                "2024-10-27T02:00:00+02:00",
+                "2024-10-27T03:00:00+01:00",
+                "2024-10-27T04:00:00+01:00",
+            ]
+        );
+    }
+
+    fn fire_days(expression: &str, from: DateTime<Cet>, count: usize) -> Vec<String> {
+        let spec = spec(expression);
+        let mut at = from;
+        (0..count)
+            .map(|_| {
+                at = spec.next_after(&at).unwrap();
+                at.format("%a %d %b").to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn day_of_month_or_day_of_week() {
+        let from = cet(2024, 7, 20, 0, 0);
+        // Both restricted: the 1st or any Monday.
+        assert_eq!(
+            fire_days("0 12 1 * mon", from, 4),
+            ["Mon 22 Jul", "Mon 29 Jul", "Thu 01 Aug", "Mon 05 Aug"]
+        );
+        // Only one restricted: just that one applies.
+        assert_eq!(
+            fire_days("0 12 1 * *", from, 2),
+            ["Thu 01 Aug", "Sun 01 Sep"]
+        );
+        assert_eq!(
+            fire_days("0 12 * * 1", from, 2),
+            ["Mon 22 Jul", "Mon 29 Jul"]
+        );
+        // 7 is Sunday as well as 0.
+        assert_eq!(fire_days("0 12 * * 7", from, 1), ["Sun 21 Jul"]);
+    }
+
+    #[test]
+    fn parses_ranges_steps_and_names() {
+        let spec = spec("*/15 9-17 * jan-mar mon-fri");
+        let fire = spec.next_after(&cet(2024, 3, 29, 17, 50)).unwrap();
+        // Friday evening is past the window and April is not allowed.
+        assert_eq!(fire.to_rfc3339(), "2025-01-01T09:00:00+01:00");
+        assert!(CronSpec::parse("61 * * * *").is_err());
+        assert!(CronSpec::parse("* * *").is_err());
+        assert!(CronSpec::parse("*/0 * * * *").is_err());
+        assert!(spec.next_after(&cet(2024, 1, 2, 9, 0)).unwrap().minute() == 15);
+    }
+
+    #[test]
+    fn upcoming_follows_the_clock() {
+        let schedule = CompiledSchedule::compile(&Schedule::TimesOfDay {
+            times: vec!["09:00".to_string(), "18:00".to_string()],
+            days: String::new(),
+        })
+        .unwrap();
+        let start = Local.with_ymd_and_hms(2024, 6, 12, 10, 0, 0).unwrap();
+        let clock = ManualClock::at(start);
+        let hours = |times: Vec<DateTime<Local>>| -> Vec<(u32, u32)> {
+            times.iter().map(|t| (t.day(), t.hour())).collect()
+        };
+        assert_eq!(
+            hours(schedule.upcoming(&clock, 3)),
+            [(12, 18), (13, 9), (13, 18)]
+        );
+
+        clock.advance(Duration::hours(9));
+        assert_eq!(hours(schedule.upcoming(&clock, 1)), [(13, 9)]);
+        // A time that is due right now has already fired.
+        clock.advance(Duration::hours(14));
+        assert_eq!(hours(schedule.upcoming(&clock, 1)), [(13, 18)]);
+    }
+}
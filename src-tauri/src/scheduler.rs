@@ -1,12 +1,284 @@
-use tauri::{AppHandle, Emitter};
+use async_trait::async_trait;
+use chrono::{DateTime, Local, TimeDelta, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
 
-fn get_interval_secs(interval: &str) -> u64 {
-    match interval {
-        "30min" => 30 * 60,
-        "1hour" => 60 * 60,
-        "6hour" => 6 * 60 * 60,
-        "daily" => 24 * 60 * 60,
-        _ => 0,
+use crate::dedupe::RecentlyShown;
+use crate::schedule::{Clock, CompiledSchedule, SystemClock};
+use crate::settings::{MissedRunPolicy, SourceConfig};
+use crate::sources::Candidate;
+
+// Pause before retrying after a scheduled rotation fails.
+const RETRY_SECS: u64 = 15 * 60;
+// Longest single sleep while waiting for a scheduled fire time.
+const CLOCK_CHECK_SECS: u64 = 30;
+const MAX_NESTING: usize = 4;
+// Redraws allowed when a source keeps offering banned or recently shown photos.
+const MAX_REDRAWS: usize = 10;
+
+// Resolves the next photo and applies it entirely in Rust. The UI still receives
+// `next-wallpaper` and the usual `wallpaper-status` events for display.
+// `scheduled` marks timer-driven rotation, which must leave an API quota reserve.
+pub async fn rotate(app: &AppHandle, scheduled: bool) -> Result<bool, String> {
+    let settings = crate::settings::load_settings_inner(app);
+    let config = resolve_source(app, settings.effective_source())?;
+    // Wallpaper sets show prepared frames instead of drawing photos.
+    if let SourceConfig::DynamicSet(options) = &config {
+        let complete = crate::dynamic::show_current_frame(app, options).await?;
+        record_change(app, Local::now());
+        return Ok(complete);
+    }
+    let source = crate::sources::build(app, &config, scheduled)?;
+    let bans = crate::bans::BanList::load(app);
+    let window = no_repeat_window(settings.no_repeat_window, source.pool_size());
+    let recent = RecentlyShown::load(app, window);
+
+    let mut last = None;
+    // The recently shown pick that was on screen longest ago.
+    let mut oldest: Option<(usize, Candidate)> = None;
+    for _ in 0..MAX_REDRAWS {
+        let candidate = source.random().await?;
+        if bans.is_banned(&candidate) || recent.contains(&candidate) {
+            if let Some(age) = recent.age(&candidate).filter(|&age| age > 0) {
+                if !bans.is_banned(&candidate) && oldest.as_ref().is_none_or(|(a, _)| age > *a) {
+                    oldest = Some((age, candidate.clone()));
+                }
+            }
+            // A source that offers the same photo twice in a row has nothing else.
+            if last
+                .as_ref()
+                .is_some_and(|l: &Candidate| l.source == candidate.source && l.id == candidate.id)
+            {
+                break;
+            }
+            last = Some(candidate);
+            continue;
+        }
+        let _ = app.emit("next-wallpaper", &candidate);
+        match crate::wallpaper::apply_unless_seen(app, source.as_ref(), &candidate, Some(&recent))
+            .await?
+        {
+            Some(complete) => {
+                record_change(app, Local::now());
+                return Ok(complete);
+            }
+            None => last = Some(candidate),
+        }
+    }
+    // Everything drawn was shown recently, so repeat the one shown longest ago
+    // rather than stopping rotation.
+    if let Some((_, candidate)) = oldest {
+        let _ = app.emit("next-wallpaper", &candidate);
+        if let Some(complete) =
+            crate::wallpaper::apply_unless_seen(app, source.as_ref(), &candidate, None).await?
+        {
+            record_change(app, Local::now());
+            return Ok(complete);
+        }
+    }
+    // Single-photo sources such as picture-of-the-day feeds keep offering what
+    // is already on screen, which just means there is nothing new yet.
+    if last.is_some_and(|c| recent.is_current(&c) && !bans.is_banned(&c)) {
+        record_change(app, Local::now());
+        return Ok(true);
+    }
+    Err(format!(
+        "{} has nothing new to show: every photo offered was banned or shown recently",
+        source.key()
+    ))
+}
+
+// A window as large as the source's pool would leave nothing to show, so it
+// stops short of the whole pool. Still at least 1, so a single-image source
+// is not re-applied over itself.
+fn no_repeat_window(configured: usize, pool_size: Option<usize>) -> usize {
+    match pool_size {
+        Some(size) => configured.min(size.saturating_sub(1).max(1)),
+        None => configured,
+    }
+}
+
+// Playlists and day/night sets stand for one of their entries at any moment;
+// entries may themselves be sets, including wallpaper sets.
+fn resolve_source(app: &AppHandle, mut config: SourceConfig) -> Result<SourceConfig, String> {
+    for _ in 0..MAX_NESTING {
+        config = match config {
+            SourceConfig::Playlist(playlist) => {
+                app.state::<crate::AppState>().playlist.next(&playlist)?
+            }
+            SourceConfig::Solar(options) => crate::solar::current_source(&options)?,
+            config => return Ok(config),
+        };
+    }
+    Err("Sources are nested too deeply".to_string())
+}
+
+pub async fn rotate_and_report(app: &AppHandle, scheduled: bool) {
+    if let Err(e) = rotate(app, scheduled).await {
+        let _ = app.emit("scheduler-error", e);
+    }
+}
+
+// When rotation happens. Every variant is measured from the last change, which
+// is persisted, so restarts and sleep do not reset the countdown.
+enum Timing {
+    Every(TimeDelta),
+    Schedule(CompiledSchedule),
+    // Picture-of-the-day feeds change once a day, so they refresh whenever the
+    // local date differs from the day of the last change.
+    DateChange,
+    // Day/night sets switch at every solar phase change, plus whatever interval
+    // or schedule applies within a phase.
+    Solar {
+        latitude: f64,
+        longitude: f64,
+        twilight: bool,
+        within: Option<Box<Timing>>,
+    },
+    // Wallpaper sets change exactly when their next frame takes over.
+    Frames(crate::dynamic::FrameTimes),
+}
+
+fn earliest(a: Option<DateTime<Local>>, b: Option<DateTime<Local>>) -> Option<DateTime<Local>> {
+    a.into_iter().chain(b).min()
+}
+
+impl Timing {
+    // A due time in the past means a change was missed.
+    fn due_after(
+        &self,
+        last: Option<DateTime<Local>>,
+        now: DateTime<Local>,
+    ) -> Option<DateTime<Local>> {
+        let Some(last) = last else {
+            return Some(now);
+        };
+        match self {
+            Timing::Every(period) => Some(last + *period),
+            Timing::Schedule(schedule) => schedule.next_after(&last),
+            Timing::DateChange => next_midnight(last),
+            Timing::Solar { within, .. } => earliest(
+                self.next_solar_change(last),
+                within.as_ref().and_then(|w| w.due_after(Some(last), now)),
+            ),
+            Timing::Frames(frames) => frames.next_change(last),
+        }
+    }
+
+    // The first due time after `now`, skipping any that were missed. Intervals
+    // stay aligned to the last change.
+    fn due_skipping_missed(
+        &self,
+        last: DateTime<Local>,
+        now: DateTime<Local>,
+    ) -> Option<DateTime<Local>> {
+        match self {
+            Timing::Every(period) => {
+                let periods = (now - last).num_seconds() / period.num_seconds().max(1) + 1;
+                Some(last + *period * periods as i32)
+            }
+            Timing::Schedule(schedule) => schedule.next_after(&now),
+            Timing::DateChange => next_midnight(now),
+            Timing::Solar { within, .. } => earliest(
+                self.next_solar_change(now),
+                within
+                    .as_ref()
+                    .and_then(|w| w.due_skipping_missed(last, now)),
+            ),
+            Timing::Frames(frames) => frames.next_change(now),
+        }
+    }
+
+    fn next_solar_change(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let Timing::Solar {
+            latitude,
+            longitude,
+            twilight,
+            ..
+        } = self
+        else {
+            return None;
+        };
+        crate::solar::next_transition(after.to_utc(), *latitude, *longitude, *twilight)
+            .map(|(at, _)| at.with_timezone(&Local))
+    }
+}
+
+fn next_midnight(after: DateTime<Local>) -> Option<DateTime<Local>> {
+    let midnight = after.date_naive().succ_opt()?.and_hms_opt(0, 0, 0)?;
+    // Some zones skip midnight when clocks go forward.
+    Local.from_local_datetime(&midnight).earliest().or_else(|| {
+        Local
+            .from_local_datetime(&(midnight + TimeDelta::hours(1)))
+            .earliest()
+    })
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct SchedulerState {
+    /// RFC 3339 time of the last rotation.
+    #[serde(default)]
+    last_change: Option<String>,
+}
+
+fn state_path(app: &AppHandle) -> PathBuf {
+    app.path()
+        .app_data_dir()
+        .unwrap()
+        .join("scheduler_state.json")
+}
+
+fn last_change(app: &AppHandle) -> Option<DateTime<Local>> {
+    let data = fs::read_to_string(state_path(app)).ok()?;
+    let state: SchedulerState = serde_json::from_str(&data).ok()?;
+    DateTime::parse_from_rfc3339(&state.last_change?)
+        .ok()
+        .map(|t| t.with_timezone(&Local))
+}
+
+fn record_change(app: &AppHandle, at: DateTime<Local>) {
+    let state = SchedulerState {
+        last_change: Some(at.to_rfc3339()),
+    };
+    let path = state_path(app);
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::to_string(&state).map_err(|e| e.to_string()))
+        .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        eprintln!("[WallCraft] failed to save scheduler state: {e}");
+    }
+}
+
+// What the timed loop drives: the app, or a stand-in in tests.
+#[async_trait]
+trait Rotator: Send + Sync {
+    fn last_change(&self) -> Option<DateTime<Local>>;
+    fn record_change(&self, at: DateTime<Local>);
+    async fn rotate(&self) -> Result<bool, String>;
+    fn report(&self, error: String);
+}
+
+#[async_trait]
+impl Rotator for AppHandle {
+    fn last_change(&self) -> Option<DateTime<Local>> {
+        last_change(self)
+    }
+
+    fn record_change(&self, at: DateTime<Local>) {
+        record_change(self, at)
+    }
+
+    async fn rotate(&self) -> Result<bool, String> {
+        rotate(self, true).await
+    }
+
+    fn report(&self, error: String) {
+        let _ = self.emit("scheduler-error", error);
     }
 }
 
@@ -17,19 +289,109 @@ pub fn start_scheduler_inner(
 ) {
     stop_scheduler_inner(state);
 
-    let secs = get_interval_secs(&settings.update_interval);
-    if secs == 0 {
-        return;
+    let base = if let Some(schedule) = &settings.schedule {
+        match CompiledSchedule::compile(schedule) {
+            Ok(compiled) => Some(Timing::Schedule(compiled)),
+            Err(e) => {
+                let _ = app.emit("scheduler-error", e);
+                return;
+            }
+        }
+    } else {
+        match settings.update_interval.period() {
+            None => None,
+            Some(_) if matches!(settings.effective_source(), SourceConfig::Daily(_)) => {
+                Some(Timing::DateChange)
+            }
+            Some(period) => Some(Timing::Every(period)),
+        }
+    };
+    let timing = match (settings.effective_source(), base) {
+        (SourceConfig::Solar(options), within) => {
+            if let Err(e) = crate::solar::validate(&options) {
+                let _ = app.emit("scheduler-error", e);
+                return;
+            }
+            Timing::Solar {
+                latitude: options.latitude,
+                longitude: options.longitude,
+                twilight: options.uses_twilight(),
+                within: within.map(Box::new),
+            }
+        }
+        (SourceConfig::DynamicSet(options), _) => {
+            match crate::dynamic::FrameTimes::load(&app, &options) {
+                Ok(frames) => Timing::Frames(frames),
+                Err(e) => {
+                    let _ = app.emit("scheduler-error", e);
+                    return;
+                }
+            }
+        }
+        (_, Some(timing)) => timing,
+        (_, None) => return,
+    };
+
+    let handle = tokio::spawn(run_timed(
+        app,
+        timing,
+        settings.missed_run_policy,
+        SystemClock,
+    ));
+    *state.scheduler_abort.lock().unwrap() = Some(handle.abort_handle());
+}
+
+async fn run_timed(
+    target: impl Rotator,
+    timing: Timing,
+    policy: MissedRunPolicy,
+    clock: impl Clock,
+) {
+    // A fresh install has nothing to count from, so the countdown starts now.
+    // Picture-of-the-day feeds, day/night and wallpaper sets instead show the
+    // right image straight away.
+    if target.last_change().is_none() && matches!(timing, Timing::Every(_) | Timing::Schedule(_)) {
+        target.record_change(clock.now());
     }
 
-    let handle = tokio::spawn(async move {
-        loop {
-            tokio::time::sleep(std::time::Duration::from_secs(secs)).await;
-            let _ = app.emit("next-wallpaper", ());
+    loop {
+        let last = target.last_change();
+        let now = clock.now();
+        let due = match (timing.due_after(last, now), last) {
+            (Some(due), Some(last)) if due <= now && policy == MissedRunPolicy::Skip => {
+                timing.due_skipping_missed(last, now)
+            }
+            (due, _) => due,
+        };
+        let Some(due) = due else {
+            target.report("Schedule never fires".to_string());
+            return;
+        };
+
+        wait_until(&clock, due).await;
+        if let Err(e) = target.rotate().await {
+            target.report(e);
+            // The last change did not move, so without a pause this would retry
+            // a failing source in a tight loop.
+            clock
+                .sleep(std::time::Duration::from_secs(RETRY_SECS))
+                .await;
         }
-    });
+    }
+}
 
-    *state.scheduler_abort.lock().unwrap() = Some(handle.abort_handle());
+// Waits on the wall clock rather than a single long sleep, so a due time is not
+// pushed back by however long the machine was asleep.
+async fn wait_until(clock: &impl Clock, due: DateTime<Local>) {
+    loop {
+        let remaining = (due - clock.now()).to_std().unwrap_or_default();
+        if remaining.is_zero() {
+            return;
+        }
+        clock
+            .sleep(remaining.min(std::time::Duration::from_secs(CLOCK_CHECK_SECS)))
+            .await;
+    }
 }
 
 pub fn stop_scheduler_inner(state: &crate::AppState) {
@@ -38,13 +400,31 @@ pub fn stop_scheduler_inner(state: &crate::AppState) {
     }
 }
 
-#[tauri::command]
-pub fn start_scheduler_cmd(
-    app: tauri::AppHandle,
-    state: tauri::State<'_, crate::AppState>,
+// Settings that decide when rotation happens, as named in settings.json. The
+// source counts because day/night and wallpaper sets bring their own timing.
+const TIMING_FIELDS: &[&str] = &["updateInterval", "schedule", "missedRunPolicy", "source"];
+
+/// Restarts the scheduler when a settings change affects its timing; other
+/// settings are read fresh on every rotation.
+pub fn on_settings_changed(
+    state: &crate::AppState,
+    settings: &crate::settings::Settings,
+    changed: &[String],
+    app: AppHandle,
 ) {
+    if !changed.iter().any(|f| TIMING_FIELDS.contains(&f.as_str())) {
+        return;
+    }
+    stop_scheduler_inner(state);
+    if settings.scheduling_enabled() {
+        start_scheduler_inner(state, settings.clone(), app);
+    }
+}
+
+#[tauri::command]
+pub fn start_scheduler_cmd(app: tauri::AppHandle, state: tauri::State<'_, crate::AppState>) {
     let settings = crate::settings::load_settings_inner(&app);
-    if settings.update_interval != "manual" {
+    if settings.scheduling_enabled() {
         start_scheduler_inner(&state, settings, app);
     }
 }
@@ -53,3 +433,187 @@ pub fn start_scheduler_cmd(
 pub fn stop_scheduler_cmd(state: tauri::State<'_, crate::AppState>) {
     stop_scheduler_inner(&state);
 }
+
+#[tauri::command]
+pub async fn rotate_now(app: AppHandle) -> Result<bool, String> {
+    rotate(&app, false).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::ManualClock;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::mpsc;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2026, 6, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    // Rotates instantly and reports when it did.
+    struct FakeApp {
+        clock: Arc<ManualClock>,
+        last: Mutex<Option<DateTime<Local>>>,
+        rotated: mpsc::UnboundedSender<DateTime<Local>>,
+    }
+
+    #[async_trait]
+    impl Rotator for FakeApp {
+        fn last_change(&self) -> Option<DateTime<Local>> {
+            *self.last.lock().unwrap()
+        }
+
+        fn record_change(&self, at: DateTime<Local>) {
+            *self.last.lock().unwrap() = Some(at);
+        }
+
+        async fn rotate(&self) -> Result<bool, String> {
+            let now = self.clock.now();
+            self.record_change(now);
+            let _ = self.rotated.send(now);
+            Ok(true)
+        }
+
+        fn report(&self, error: String) {
+            panic!("scheduler error: {error}");
+        }
+    }
+
+    // Starts the timed loop at `now` after a last change at `last`, and returns
+    // the times of the first `count` rotations.
+    async fn rotations(
+        timing: Timing,
+        policy: MissedRunPolicy,
+        last: Option<DateTime<Local>>,
+        now: DateTime<Local>,
+        count: usize,
+    ) -> Vec<DateTime<Local>> {
+        let clock = Arc::new(ManualClock::at(now));
+        let (rotated, mut times) = mpsc::unbounded_channel();
+        let app = FakeApp {
+            clock: clock.clone(),
+            last: Mutex::new(last),
+            rotated,
+        };
+        let task = tokio::spawn(run_timed(app, timing, policy, clock));
+        let mut seen = Vec::new();
+        while seen.len() < count {
+            seen.push(times.recv().await.unwrap());
+        }
+        task.abort();
+        seen
+    }
+
+    fn hourly() -> Timing {
+        Timing::Every(TimeDelta::hours(1))
+    }
+
+    #[test]
+    fn missed_changes_are_due_in_the_past() {
+        // Closed overnight: yesterday's picture of the day is out of date.
+        let (last, now) = (at(9, 9, 0), at(10, 10, 0));
+        assert_eq!(
+            Timing::DateChange.due_after(Some(last), now),
+            Some(at(10, 0, 0))
+        );
+        assert_eq!(
+            Timing::DateChange.due_skipping_missed(last, now),
+            Some(at(11, 0, 0))
+        );
+
+        // Skipping keeps hourly changes on the hour they started on.
+        let (last, now) = (at(10, 8, 0), at(10, 10, 30));
+        assert_eq!(hourly().due_after(Some(last), now), Some(at(10, 9, 0)));
+        assert_eq!(hourly().due_skipping_missed(last, now), Some(at(10, 11, 0)));
+
+        // Nothing to count from, so due straight away.
+        assert_eq!(hourly().due_after(None, now), Some(now));
+    }
+
+    #[tokio::test]
+    async fn a_missed_daily_change_runs_at_startup() {
+        let times = rotations(
+            Timing::DateChange,
+            MissedRunPolicy::RunImmediately,
+            Some(at(9, 9, 0)),
+            at(10, 10, 0),
+            2,
+        )
+        .await;
+        assert_eq!(times, [at(10, 10, 0), at(11, 0, 0)]);
+    }
+
+    #[tokio::test]
+    async fn skipped_changes_wait_for_the_next_due_time() {
+        let daily = rotations(
+            Timing::DateChange,
+            MissedRunPolicy::Skip,
+            Some(at(9, 9, 0)),
+            at(10, 10, 0),
+            1,
+        )
+        .await;
+        assert_eq!(daily, [at(11, 0, 0)]);
+
+        let hourly_times = rotations(
+            hourly(),
+            MissedRunPolicy::Skip,
+            Some(at(10, 8, 0)),
+            at(10, 10, 30),
+            2,
+        )
+        .await;
+        assert_eq!(hourly_times, [at(10, 11, 0), at(10, 12, 0)]);
+
+        let caught_up = rotations(
+            hourly(),
+            MissedRunPolicy::RunImmediately,
+            Some(at(10, 8, 0)),
+            at(10, 10, 30),
+            2,
+        )
+        .await;
+        assert_eq!(caught_up, [at(10, 10, 30), at(10, 11, 30)]);
+    }
+
+    #[tokio::test]
+    async fn restarting_before_the_due_time_keeps_the_countdown() {
+        for policy in [MissedRunPolicy::RunImmediately, MissedRunPolicy::Skip] {
+            let times = rotations(hourly(), policy, Some(at(10, 10, 0)), at(10, 10, 20), 2).await;
+            assert_eq!(times, [at(10, 11, 0), at(10, 12, 0)]);
+        }
+        let daily = rotations(
+            Timing::DateChange,
+            MissedRunPolicy::RunImmediately,
+            Some(at(10, 9, 0)),
+            at(10, 10, 0),
+            1,
+        )
+        .await;
+        assert_eq!(daily, [at(11, 0, 0)]);
+
+        // A fresh install starts counting rather than changing at once.
+        let fresh = rotations(
+            hourly(),
+            MissedRunPolicy::RunImmediately,
+            None,
+            at(10, 10, 20),
+            1,
+        )
+        .await;
+        assert_eq!(fresh, [at(10, 11, 20)]);
+    }
+
+    #[test]
+    fn no_repeat_window_stops_short_of_the_pool() {
+        assert_eq!(no_repeat_window(50, None), 50);
+        assert_eq!(no_repeat_window(50, Some(1000)), 50);
+        // Ten files: the next one in order was shown ten rotations ago.
+        assert_eq!(no_repeat_window(50, Some(10)), 9);
+        assert_eq!(no_repeat_window(50, Some(1)), 1);
+        assert_eq!(no_repeat_window(50, Some(0)), 1);
+        assert_eq!(no_repeat_window(0, Some(10)), 0);
+    }
+}
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use tauri::WebviewWindow;
+use tauri::AppHandle;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -13,9 +13,8 @@ pub struct ScreenInfo {
     pub scale_factor: f64,
 }
 
-pub fn collect_screens(window: &WebviewWindow) -> Vec<ScreenInfo> {
-    window
-        .available_monitors()
+pub fn collect_screens(app: &AppHandle) -> Vec<ScreenInfo> {
+    app.available_monitors()
         .unwrap_or_default()
         .into_iter()
         .enumerate()
@@ -42,11 +41,11 @@ pub fn collect_screens(window: &WebviewWindow) -> Vec<ScreenInfo> {
 }
 
 #[tauri::command]
-pub fn get_screens(window: WebviewWindow) -> Vec<ScreenInfo> {
-    collect_screens(&window)
+pub fn get_screens(app: AppHandle) -> Vec<ScreenInfo> {
+    collect_screens(&app)
 }
 
 #[tauri::command]
-pub fn refresh_screens(window: WebviewWindow) -> Vec<ScreenInfo> {
-    collect_screens(&window)
+pub fn refresh_screens(app: AppHandle) -> Vec<ScreenInfo> {
+    collect_screens(&app)
 }
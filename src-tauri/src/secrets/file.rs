@@ -0,0 +1,291 @@
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use super::SecretBackend;
+
+const FILE_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// On-disk form: every secret in one JSON map, sealed with XChaCha20-Poly1305
+/// under a key derived by Argon2id.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SealedFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Secrets in an encrypted file, keyed by a passphrase or by the machine's id.
+pub struct FileBackend {
+    path: PathBuf,
+    salt: Vec<u8>,
+    cipher: XChaCha20Poly1305,
+    // Serialises read-modify-write cycles on the file.
+    lock: Mutex<()>,
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+fn decode(field: &str, value: &str) -> Result<Vec<u8>, String> {
+    STANDARD
+        .decode(value)
+        .map_err(|e| format!("Corrupt secret store ({field}): {e}"))
+}
+
+fn read_sealed(path: &Path) -> Result<Option<SealedFile>, String> {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Cannot read secret store: {e}")),
+    };
+    let sealed: SealedFile =
+        serde_json::from_str(&data).map_err(|e| format!("Corrupt secret store: {e}"))?;
+    if sealed.version > FILE_VERSION {
+        return Err(format!(
+            "Secret store version {} is newer than this app supports",
+            sealed.version
+        ));
+    }
+    Ok(Some(sealed))
+}
+
+// Owner-only permissions where the platform has them; the content is
+// encrypted either way.
+fn create_private(path: &Path) -> std::io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+impl FileBackend {
+    /// Derives the key from `material` and checks it against an existing file.
+    /// A missing file is created on the first write.
+    pub fn open(path: PathBuf, material: &[u8]) -> Result<Self, String> {
+        let sealed = read_sealed(&path)?;
+        let salt = match &sealed {
+            Some(sealed) => decode("salt", &sealed.salt)?,
+            None => random_bytes(SALT_LEN),
+        };
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(material, &salt, &mut key)
+            .map_err(|e| format!("Cannot derive secret store key: {e}"))?;
+        let backend = FileBackend {
+            path,
+            salt,
+            cipher: XChaCha20Poly1305::new(Key::from_slice(&key)),
+            lock: Mutex::new(()),
+        };
+        key.fill(0);
+        if let Some(sealed) = sealed {
+            backend.unseal(&sealed)?;
+        }
+        Ok(backend)
+    }
+
+    fn unseal(&self, sealed: &SealedFile) -> Result<HashMap<String, String>, String> {
+        let nonce = decode("nonce", &sealed.nonce)?;
+        if nonce.len() != NONCE_LEN {
+            return Err("Corrupt secret store (nonce)".to_string());
+        }
+        let ciphertext = decode("ciphertext", &sealed.ciphertext)?;
+        let plaintext = self
+            .cipher
+            .decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| {
+                "Cannot decrypt the secret store: wrong passphrase or a different machine"
+                    .to_string()
+            })?;
+        serde_json::from_slice(&plaintext).map_err(|e| format!("Corrupt secret store: {e}"))
+    }
+
+    fn load(&self) -> Result<HashMap<String, String>, String> {
+        match read_sealed(&self.path)? {
+            Some(sealed) => self.unseal(&sealed),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    // A fresh nonce for every write; the file is replaced atomically.
+    fn save(&self, secrets: &HashMap<String, String>) -> Result<(), String> {
+        let plaintext = serde_json::to_vec(secrets).map_err(|e| e.to_string())?;
+        let nonce = random_bytes(NONCE_LEN);
+        let ciphertext = self
+            .cipher
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_ref())
+            .map_err(|_| "Cannot encrypt the secret store".to_string())?;
+        let sealed = SealedFile {
+            version: FILE_VERSION,
+            salt: STANDARD.encode(&self.salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+        let json = serde_json::to_vec_pretty(&sealed).map_err(|e| e.to_string())?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let temp = self.path.with_extension("tmp");
+        let written = create_private(&temp).and_then(|mut file| {
+            file.write_all(&json)?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|_| fs::rename(&temp, &self.path)) {
+            let _ = fs::remove_file(&temp);
+            return Err(format!("Cannot save secret store: {e}"));
+        }
+        Ok(())
+    }
+}
+
+impl SecretBackend for FileBackend {
+    fn get(&self, id: &str) -> Result<Option<String>, String> {
+        Ok(self.load()?.remove(id))
+    }
+
+    fn set(&self, id: &str, value: &str) -> Result<(), String> {
+        let _guard = self.lock.lock().unwrap();
+        let mut secrets = self.load()?;
+        if secrets.get(id).map(String::as_str) == Some(value) {
+            return Ok(());
+        }
+        secrets.insert(id.to_string(), value.to_string());
+        self.save(&secrets)
+    }
+
+    fn delete(&self, id: &str) -> Result<(), String> {
+        let _guard = self.lock.lock().unwrap();
+        let mut secrets = self.load()?;
+        if secrets.remove(id).is_some() {
+            self.save(&secrets)?;
+        }
+        Ok(())
+    }
+}
+
+/// A stable per-machine identifier to derive the machine key from. It ties the
+/// file to this machine; it is not a substitute for a passphrase.
+pub fn machine_id() -> Result<String, String> {
+    #[cfg(target_os = "linux")]
+    {
+        ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+            .iter()
+            .find_map(|path| {
+                fs::read_to_string(path)
+                    .ok()
+                    .map(|id| id.trim().to_string())
+                    .filter(|id| !id.is_empty())
+            })
+            .ok_or_else(|| "This machine has no machine-id".to_string())
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let output = std::process::Command::new("ioreg")
+            .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+            .output()
+            .map_err(|e| format!("Cannot read the hardware UUID: {e}"))?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find(|line| line.contains("IOPlatformUUID"))
+            .and_then(|line| line.split('"').nth(3))
+            .map(str::to_string)
+            .ok_or_else(|| "Cannot read the hardware UUID".to_string())
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let output = std::process::Command::new("reg")
+            .args([
+                "query",
+                r"HKLM\SOFTWARE\Microsoft\Cryptography",
+                "/v",
+                "MachineGuid",
+            ])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map_err(|e| format!("Cannot read MachineGuid: {e}"))?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find(|line| line.contains("MachineGuid"))
+            .and_then(|line| line.split_whitespace().last())
+            .map(str::to_string)
+            .ok_or_else(|| "Cannot read MachineGuid".to_string())
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        Err("No machine id on this platform; use a passphrase".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wallcraft-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("secrets.passphrase.json")
+    }
+
+    #[test]
+    fn secrets_survive_reopening() {
+        let path = store_path("file-backend");
+        let store = FileBackend::open(path.clone(), b"correct horse").unwrap();
+        store.set("a", "unsplash-key").unwrap();
+        store.set("b", "pexels-key").unwrap();
+        assert_eq!(store.get("a").unwrap().as_deref(), Some("unsplash-key"));
+
+        let reopened = FileBackend::open(path.clone(), b"correct horse").unwrap();
+        assert_eq!(reopened.get("b").unwrap().as_deref(), Some("pexels-key"));
+        reopened.delete("a").unwrap();
+        assert_eq!(store.get("a").unwrap(), None);
+        assert_eq!(reopened.get("missing").unwrap(), None);
+
+        // Only sealed data is written out.
+        let on_disk = fs::read_to_string(&path).unwrap();
+        assert!(!on_disk.contains("pexels-key"));
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn wrong_passphrases_cannot_open_the_store() {
+        let path = store_path("wrong-passphrase");
+        let store = FileBackend::open(path.clone(), b"correct horse").unwrap();
+        store.set("a", "unsplash-key").unwrap();
+
+        let error = FileBackend::open(path.clone(), b"battery staple")
+            .err()
+            .unwrap();
+        assert!(error.contains("wrong passphrase"), "{error}");
+        // Nothing was overwritten by the failed attempt.
+        let store = FileBackend::open(path.clone(), b"correct horse").unwrap();
+        assert_eq!(store.get("a").unwrap().as_deref(), Some("unsplash-key"));
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,468 @@
+mod file;
+mod os_keyring;
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::settings::{default_nasa_api_key, DailyFeed, SecretStorage, Settings, SourceConfig};
+
+/// Settings hold `secret:<id>` in place of a secret; the value lives in the
+/// secret store under `id`.
+const SECRET_REF_PREFIX: &str = "secret:";
+const MACHINE_FILE: &str = "secrets.machine.json";
+const PASSPHRASE_FILE: &str = "secrets.passphrase.json";
+const INDEX_FILE: &str = "secrets_index.json";
+
+/// Somewhere secrets can be kept, under random ids that the references in
+/// settings carry. Ids do not depend on where a key sits in settings, so
+/// reordering profiles or playlist entries cannot mix keys up.
+pub trait SecretBackend: Send + Sync {
+    fn get(&self, id: &str) -> Result<Option<String>, String>;
+    fn set(&self, id: &str, value: &str) -> Result<(), String>;
+    fn delete(&self, id: &str) -> Result<(), String>;
+}
+
+// Opened backends live in AppState so keys are derived once per run.
+#[derive(Default)]
+pub struct SecretStore {
+    open: Mutex<Option<(SecretStorage, Arc<dyn SecretBackend>)>>,
+    // Set by `unlock_secret_store`; the passphrase itself is never kept.
+    unlocked: Mutex<Option<Arc<file::FileBackend>>>,
+}
+
+/// Where secrets are kept: the folder with the index and file stores, and the
+/// backends themselves. The app is the real one; tests bring their own.
+pub trait SecretHome {
+    fn dir(&self) -> PathBuf;
+    fn open(
+        &self,
+        storage: SecretStorage,
+    ) -> Result<(SecretStorage, Arc<dyn SecretBackend>), String>;
+    /// Tells the user that secrets could not be read.
+    fn report(&self, error: &str);
+}
+
+impl SecretHome for AppHandle {
+    fn dir(&self) -> PathBuf {
+        self.path().app_data_dir().unwrap()
+    }
+
+    fn open(
+        &self,
+        storage: SecretStorage,
+    ) -> Result<(SecretStorage, Arc<dyn SecretBackend>), String> {
+        open(self, storage)
+    }
+
+    fn report(&self, error: &str) {
+        let _ = self.emit("secret-store-error", error);
+    }
+}
+
+fn data_path(app: &AppHandle, name: &str) -> PathBuf {
+    app.path().app_data_dir().unwrap().join(name)
+}
+
+// `Auto` becomes the keyring where one answers, otherwise the machine-key file.
+fn concrete(storage: SecretStorage) -> SecretStorage {
+    match storage {
+        SecretStorage::Auto if os_keyring::available() => SecretStorage::Keyring,
+        SecretStorage::Auto => SecretStorage::MachineKeyFile,
+        other => other,
+    }
+}
+
+fn open(
+    app: &AppHandle,
+    storage: SecretStorage,
+) -> Result<(SecretStorage, Arc<dyn SecretBackend>), String> {
+    let store = app
+        .try_state::<crate::AppState>()
+        .ok_or("The secret store is not ready")?;
+    let mut open = store.secrets.open.lock().unwrap();
+    if let Some((kind, backend)) = open.as_ref() {
+        if storage == SecretStorage::Auto || *kind == storage {
+            return Ok((*kind, backend.clone()));
+        }
+    }
+    let kind = concrete(storage);
+    let backend: Arc<dyn SecretBackend> = match kind {
+        SecretStorage::PassphraseFile => store
+            .secrets
+            .unlocked
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or("The secret store is locked; enter its passphrase")?,
+        SecretStorage::MachineKeyFile => {
+            let material = format!("WallCraft:{}", file::machine_id()?);
+            Arc::new(file::FileBackend::open(
+                data_path(app, MACHINE_FILE),
+                material.as_bytes(),
+            )?)
+        }
+        SecretStorage::Keyring | SecretStorage::Auto => Arc::new(os_keyring::KeyringBackend),
+    };
+    *open = Some((kind, backend.clone()));
+    Ok((kind, backend))
+}
+
+// Calls `f` with the setting path and value of every secret in `settings`.
+fn visit(settings: &mut Settings, f: &mut dyn FnMut(String, &mut String)) {
+    f("apiKey".to_string(), &mut settings.api_key);
+    if let Some(source) = &mut settings.source {
+        visit_source("source", source, f);
+    }
+    for (i, profile) in settings.profiles.iter_mut().enumerate() {
+        if let Some(source) = &mut profile.source {
+            visit_source(&format!("profiles[{i}].source"), source, f);
+        }
+    }
+}
+
+fn visit_source(path: &str, source: &mut SourceConfig, f: &mut dyn FnMut(String, &mut String)) {
+    let field = |name: &str| format!("{path}.{name}");
+    match source {
+        SourceConfig::Pexels { api_key, .. } => f(field("apiKey"), api_key),
+        SourceConfig::Wallhaven(options) => {
+            if let Some(api_key) = &mut options.api_key {
+                f(field("apiKey"), api_key);
+            }
+        }
+        // The shared demo key is public.
+        SourceConfig::Daily(DailyFeed::NasaApod { api_key })
+            if *api_key != default_nasa_api_key() =>
+        {
+            f(field("apiKey"), api_key)
+        }
+        SourceConfig::Playlist(options) => {
+            for (i, entry) in options.entries.iter_mut().enumerate() {
+                visit_source(
+                    &field(&format!("entries[{i}].source")),
+                    &mut entry.source,
+                    f,
+                );
+            }
+        }
+        SourceConfig::Solar(options) => {
+            visit_source(&field("day"), &mut options.day, f);
+            visit_source(&field("night"), &mut options.night, f);
+            if let Some(dawn) = &mut options.dawn {
+                visit_source(&field("dawn"), dawn, f);
+            }
+            if let Some(dusk) = &mut options.dusk {
+                visit_source(&field("dusk"), dusk, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn is_reference(value: &str) -> bool {
+    value.starts_with(SECRET_REF_PREFIX)
+}
+
+fn new_id() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+const LOCKED: &str = "The secret store is locked or unavailable, so the API key cannot be read";
+
+/// Refuses a key that is still a reference because the secret store could not
+/// be read, so the reference is never sent to a provider in its place.
+pub fn ensure_resolved(value: &str) -> Result<(), String> {
+    if is_reference(value) {
+        return Err(LOCKED.to_string());
+    }
+    Ok(())
+}
+
+/// `ensure_resolved` for every key `source` and its nested sources hold.
+pub fn ensure_source_resolved(source: &SourceConfig) -> Result<(), String> {
+    let mut result = Ok(());
+    visit_source("source", &mut source.clone(), &mut |_, value| {
+        if result.is_ok() {
+            result = ensure_resolved(value);
+        }
+    });
+    result
+}
+
+/// Whether any secret is still written out in plain text.
+pub fn has_plaintext(settings: &Settings) -> bool {
+    let mut found = false;
+    visit(&mut settings.clone(), &mut |_, value| {
+        found |= !value.is_empty() && !is_reference(value);
+    });
+    found
+}
+
+/// The secrets `settings` holds in resolved form, which after a successful
+/// save are all in the store.
+pub fn stored_values(settings: &Settings) -> Vec<String> {
+    let mut values = Vec::new();
+    visit(&mut settings.clone(), &mut |_, value| {
+        if !value.is_empty() && !is_reference(value) && !values.contains(value) {
+            values.push(value.clone());
+        }
+    });
+    values
+}
+
+/// Replaces references with the stored secrets. References that cannot be
+/// resolved, such as while a passphrase store is locked, stay as they are and
+/// the problem is reported; `ensure_resolved` keeps them from being used.
+pub fn resolve(home: &dyn SecretHome, settings: &mut Settings) {
+    // Look where the references were written, which differs from the setting
+    // after it was changed by hand.
+    let storage = load_index(&home.dir())
+        .storage
+        .unwrap_or(settings.secret_storage);
+    let mut backend = None;
+    let mut failure = None;
+    visit(settings, &mut |id, value| {
+        let Some(reference) = value.strip_prefix(SECRET_REF_PREFIX) else {
+            return;
+        };
+        if failure.is_some() {
+            return;
+        }
+        if backend.is_none() {
+            match home.open(storage) {
+                Ok((_, opened)) => backend = Some(opened),
+                Err(e) => {
+                    failure = Some(e);
+                    return;
+                }
+            }
+        }
+        match backend.as_ref().unwrap().get(reference) {
+            Ok(Some(secret)) => *value = secret,
+            Ok(None) => eprintln!("[WallCraft] secret for {id} is missing from the store"),
+            Err(e) => failure = Some(e),
+        }
+    });
+    if let Some(e) = failure {
+        eprintln!("[WallCraft] cannot read secrets: {e}");
+        home.report(&e);
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct SecretIndex {
+    // Where the ids below were stored.
+    storage: Option<SecretStorage>,
+    ids: Vec<String>,
+}
+
+fn load_index(dir: &Path) -> SecretIndex {
+    fs::read_to_string(dir.join(INDEX_FILE))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(dir: &Path, index: &SecretIndex) {
+    let result = serde_json::to_string_pretty(index)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(dir.join(INDEX_FILE), json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        eprintln!("[WallCraft] failed to save secret index: {e}");
+    }
+}
+
+/// Moves every secret into the configured store and returns a copy of
+/// `settings` holding only references, ready to be written to disk. A key
+/// already in the store keeps its id; secrets that no setting refers to any
+/// more are removed from the store.
+pub fn externalize(home: &dyn SecretHome, settings: &Settings) -> Result<Settings, String> {
+    let mut on_disk = settings.clone();
+    let dir = home.dir();
+    let index = load_index(&dir);
+    if !has_plaintext(settings) && index.ids.is_empty() {
+        return Ok(on_disk);
+    }
+    let mut unresolved = false;
+    visit(&mut settings.clone(), &mut |_, value| {
+        unresolved |= is_reference(value);
+    });
+
+    let (kind, backend) = home.open(settings.secret_storage)?;
+    let moved = index.storage.is_some_and(|old| old != kind);
+    if moved && unresolved {
+        return Err("Unlock the secret store before moving secrets elsewhere".to_string());
+    }
+    // Settings in memory hold resolved keys, so match them against the store
+    // rather than minting a new id on every save.
+    let mut known: Vec<(String, String)> = if moved {
+        Vec::new()
+    } else {
+        index
+            .ids
+            .iter()
+            .filter_map(|id| Some((id.clone(), backend.get(id).ok()??)))
+            .collect()
+    };
+    let mut referenced = Vec::new();
+    let mut failure = None;
+    visit(&mut on_disk, &mut |_, value| {
+        if let Some(reference) = value.strip_prefix(SECRET_REF_PREFIX) {
+            referenced.push(reference.to_string());
+            return;
+        }
+        if value.is_empty() || failure.is_some() {
+            return;
+        }
+        let id = match known.iter().find(|(_, secret)| secret == value) {
+            Some((id, _)) => id.clone(),
+            None => {
+                let id = new_id();
+                if let Err(e) = backend.set(&id, value) {
+                    failure = Some(e);
+                }
+                known.push((id.clone(), value.clone()));
+                id
+            }
+        };
+        *value = format!("{SECRET_REF_PREFIX}{id}");
+        referenced.push(id);
+    });
+    if let Some(e) = failure {
+        return Err(e);
+    }
+    referenced.sort();
+    referenced.dedup();
+    // Clear out the previous store, or whatever this one no longer needs.
+    let stale = index
+        .ids
+        .iter()
+        .filter(|id| moved || !referenced.contains(id));
+    match index.storage.filter(|_| moved) {
+        Some(old) => {
+            let old_backend: Option<Arc<dyn SecretBackend>> = match old {
+                SecretStorage::Keyring => Some(Arc::new(os_keyring::KeyringBackend)),
+                _ => None,
+            };
+            for id in stale {
+                if let Some(old_backend) = &old_backend {
+                    let _ = old_backend.delete(id);
+                }
+            }
+            // File stores hold nothing else, so they go as a whole.
+            let old_file = match old {
+                SecretStorage::MachineKeyFile => Some(MACHINE_FILE),
+                SecretStorage::PassphraseFile => Some(PASSPHRASE_FILE),
+                _ => None,
+            };
+            if let Some(name) = old_file {
+                let _ = fs::remove_file(dir.join(name));
+            }
+        }
+        None => {
+            for id in stale {
+                if let Err(e) = backend.delete(id) {
+                    eprintln!("[WallCraft] failed to remove secret {id}: {e}");
+                }
+            }
+        }
+    }
+    save_index(
+        &dir,
+        &SecretIndex {
+            storage: Some(kind),
+            ids: referenced,
+        },
+    );
+    Ok(on_disk)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretStoreStatus {
+    /// What settings ask for.
+    pub storage: SecretStorage,
+    /// The store in use, once one has been opened.
+    pub backend: Option<SecretStorage>,
+    pub keyring_available: bool,
+    /// The passphrase store has been unlocked this run.
+    pub unlocked: bool,
+    /// Settings use the passphrase store and it is not unlocked yet.
+    pub locked: bool,
+}
+
+#[tauri::command]
+pub fn get_secret_store_status(
+    app: AppHandle,
+    state: tauri::State<'_, crate::AppState>,
+) -> SecretStoreStatus {
+    let storage = crate::settings::load_settings_inner(&app).secret_storage;
+    let unlocked = state.secrets.unlocked.lock().unwrap().is_some();
+    SecretStoreStatus {
+        storage,
+        backend: state.secrets.open.lock().unwrap().as_ref().map(|(k, _)| *k),
+        keyring_available: os_keyring::available(),
+        unlocked,
+        locked: storage == SecretStorage::PassphraseFile && !unlocked,
+    }
+}
+
+/// Opens the passphrase-encrypted store, creating it if there is none yet,
+/// and re-reads settings so their secrets resolve.
+#[tauri::command]
+pub fn unlock_secret_store(
+    app: AppHandle,
+    state: tauri::State<'_, crate::AppState>,
+    passphrase: String,
+) -> Result<(), String> {
+    if passphrase.is_empty() {
+        return Err("Enter a passphrase".to_string());
+    }
+    let backend = file::FileBackend::open(data_path(&app, PASSPHRASE_FILE), passphrase.as_bytes())?;
+    *state.secrets.unlocked.lock().unwrap() = Some(Arc::new(backend));
+    // Drop a cached handle so the next open picks up the unlocked store.
+    let mut open = state.secrets.open.lock().unwrap();
+    if open
+        .as_ref()
+        .is_some_and(|(kind, _)| *kind == SecretStorage::PassphraseFile)
+    {
+        *open = None;
+    }
+    drop(open);
+    crate::settings::reload(&app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unresolved_references_are_refused() {
+        assert!(ensure_resolved("plain-key").is_ok());
+        assert_eq!(ensure_resolved("secret:0f3a").unwrap_err(), LOCKED);
+
+        let playlist: SourceConfig = serde_json::from_value(serde_json::json!({
+            "provider": "playlist",
+            "entries": [
+                { "weight": 1, "source": { "provider": "favorites" } },
+                { "weight": 1, "source": { "provider": "pexels", "apiKey": "secret:0f3a" } }
+            ]
+        }))
+        .unwrap();
+        assert!(ensure_source_resolved(&playlist).is_err());
+        assert!(ensure_source_resolved(&SourceConfig::Favorites).is_ok());
+    }
+
+    #[test]
+    fn ids_are_random() {
+        let (a, b) = (new_id(), new_id());
+        assert_ne!(a, b);
+        assert_eq!(a.len(), 32);
+    }
+}
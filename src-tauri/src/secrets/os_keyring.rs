@@ -0,0 +1,44 @@
+use keyring::{Entry, Error};
+
+use super::SecretBackend;
+
+const SERVICE: &str = "WallCraft";
+
+/// The platform keyring: Keychain on macOS, Credential Manager on Windows and
+/// the Secret Service on Linux.
+pub struct KeyringBackend;
+
+fn entry(id: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE, id).map_err(|e| format!("Keyring unavailable: {e}"))
+}
+
+/// Whether a keyring answers at all. A missing entry still counts as working.
+pub fn available() -> bool {
+    matches!(
+        entry("availability-check").map(|e| e.get_password()),
+        Ok(Ok(_)) | Ok(Err(Error::NoEntry))
+    )
+}
+
+impl SecretBackend for KeyringBackend {
+    fn get(&self, id: &str) -> Result<Option<String>, String> {
+        match entry(id)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(Error::NoEntry) => Ok(None),
+            Err(e) => Err(format!("Cannot read {id} from the keyring: {e}")),
+        }
+    }
+
+    fn set(&self, id: &str, value: &str) -> Result<(), String> {
+        entry(id)?
+            .set_password(value)
+            .map_err(|e| format!("Cannot store {id} in the keyring: {e}"))
+    }
+
+    fn delete(&self, id: &str) -> Result<(), String> {
+        match entry(id)?.delete_password() {
+            Ok(()) | Err(Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Cannot remove {id} from the keyring: {e}")),
+        }
+    }
+}
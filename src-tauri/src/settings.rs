@@ -1,39 +1,1202 @@
+use chrono::TimeDelta;
 use serde::{Deserialize, Serialize};
-use std::fs;
-use tauri::{AppHandle, Manager};
+use serde_json::Value;
+use std::{fs, path::Path};
+use tauri::{AppHandle, Emitter, Manager};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// Schema version written by this build. Older files are migrated on load.
+pub const SETTINGS_VERSION: u32 = 2;
+
+/// Where scheduled rotation draws Unsplash photos from.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum RotationSource {
+    Editorial,
+    Topic {
+        slug: String,
+    },
+    Collection {
+        id: String,
+    },
+    User {
+        username: String,
+    },
+    Search {
+        query: String,
+    },
+    Random {
+        #[serde(default)]
+        orientation: Option<String>,
+        #[serde(default)]
+        content_filter: Option<String>,
+        #[serde(default)]
+        query: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WallhavenOptions {
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub query: String,
+    // Wallhaven's bit flags: general/anime/people and sfw/sketchy/nsfw.
+    #[serde(default = "default_wallhaven_categories")]
+    pub categories: String,
+    #[serde(default = "default_wallhaven_purity")]
+    pub purity: String,
+    // Minimum resolution such as "2560x1440"; empty means any.
+    #[serde(default)]
+    pub at_least: String,
+}
+
+fn default_wallhaven_categories() -> String {
+    "100".to_string()
+}
+
+fn default_wallhaven_purity() -> String {
+    "100".to_string()
+}
+
+/// Field locations for a generic JSON image feed, as JSON Pointers relative to
+/// each item (`items_pointer` is relative to the document root).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonFeedOptions {
+    pub url: String,
+    #[serde(default)]
+    pub items_pointer: String,
+    #[serde(default = "default_image_pointer")]
+    pub image_pointer: String,
+    #[serde(default = "default_id_pointer")]
+    pub id_pointer: String,
+    #[serde(default)]
+    pub preview_pointer: String,
+    #[serde(default)]
+    pub width_pointer: String,
+    #[serde(default)]
+    pub height_pointer: String,
+    #[serde(default)]
+    pub author_pointer: String,
+    #[serde(default)]
+    pub author_url_pointer: String,
+    #[serde(default)]
+    pub page_url_pointer: String,
+}
+
+fn default_image_pointer() -> String {
+    "/url".to_string()
+}
+
+fn default_id_pointer() -> String {
+    "/id".to_string()
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum FolderOrder {
+    #[default]
+    Shuffle,
+    Sequential,
+    NewestFirst,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalFolderOptions {
+    pub directories: Vec<String>,
+    #[serde(default)]
+    pub order: FolderOrder,
+}
+
+/// An RSS/Atom feed or a Reddit JSON listing (e.g. `/r/EarthPorn/top.json`).
+/// Posts smaller than the minimum size or outside the aspect range are skipped;
+/// posts that do not report dimensions are kept.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedOptions {
+    pub url: String,
+    #[serde(default)]
+    pub min_width: u32,
+    #[serde(default)]
+    pub min_height: u32,
+    #[serde(default)]
+    pub min_aspect: Option<f64>,
+    #[serde(default)]
+    pub max_aspect: Option<f64>,
+    #[serde(default)]
+    pub allow_nsfw: bool,
+}
+
+/// Picture-of-the-day feeds. Each publishes one image per day.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "feed", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum DailyFeed {
+    Bing {
+        #[serde(default = "default_bing_market")]
+        market: String,
+    },
+    NasaApod {
+        #[serde(default = "default_nasa_api_key")]
+        api_key: String,
+    },
+    Wikimedia {
+        #[serde(default = "default_wikimedia_language")]
+        language: String,
+    },
+}
+
+fn default_bing_market() -> String {
+    "en-US".to_string()
+}
+
+// NASA's shared demo key allows a few dozen requests per hour, plenty for once a day.
+pub fn default_nasa_api_key() -> String {
+    "DEMO_KEY".to_string()
+}
+
+fn default_wikimedia_language() -> String {
+    "en".to_string()
+}
+
+/// One source in a playlist and its relative share of rotations.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistEntry {
+    pub weight: u32,
+    pub source: SourceConfig,
+}
+
+/// Mixes several sources, e.g. 60% an Unsplash topic, 30% a local folder and
+/// 10% NASA APOD.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistOptions {
+    pub entries: Vec<PlaylistEntry>,
+    /// Longest run of picks from the same entry; 0 disables the rule.
+    #[serde(default = "default_max_in_a_row")]
+    pub max_in_a_row: u32,
+    /// Fixed seed for reproducible picks; unseeded playlists use OS entropy.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+fn default_max_in_a_row() -> u32 {
+    2
+}
+
+/// Separate sources for day and night, switched at local sunrise and sunset.
+/// Giving dawn or dusk a source of its own also switches at civil twilight.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SolarOptions {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub day: Box<SourceConfig>,
+    pub night: Box<SourceConfig>,
+    #[serde(default)]
+    pub dawn: Option<Box<SourceConfig>>,
+    #[serde(default)]
+    pub dusk: Option<Box<SourceConfig>>,
+}
+
+impl SolarOptions {
+    pub fn uses_twilight(&self) -> bool {
+        self.dawn.is_some() || self.dusk.is_some()
+    }
+}
+
+/// An imported time-of-day wallpaper set. Location is only needed for sets
+/// whose frames follow the sun.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DynamicSetOptions {
+    pub set_id: String,
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+}
+
+/// The provider that scheduled rotation pulls wallpapers from.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "provider", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum SourceConfig {
+    Unsplash {
+        rotation: RotationSource,
+    },
+    Pexels {
+        api_key: String,
+        #[serde(default)]
+        query: Option<String>,
+    },
+    Wallhaven(WallhavenOptions),
+    JsonFeed(JsonFeedOptions),
+    Feed(FeedOptions),
+    LocalFolder(LocalFolderOptions),
+    Daily(DailyFeed),
+    /// Only wallpapers starred with "favorite".
+    Favorites,
+    Playlist(PlaylistOptions),
+    Solar(SolarOptions),
+    DynamicSet(DynamicSetOptions),
+    /// One colour, given as `#rrggbb`, on every screen.
+    SolidColor {
+        color: String,
+    },
+}
+
+impl SourceConfig {
+    /// Calls `f` on this source and every source nested inside it.
+    pub fn visit_mut(&mut self, f: &mut dyn FnMut(&mut SourceConfig)) {
+        f(self);
+        match self {
+            SourceConfig::Playlist(options) => {
+                for entry in &mut options.entries {
+                    entry.source.visit_mut(f);
+                }
+            }
+            SourceConfig::Solar(options) => {
+                options.day.visit_mut(f);
+                options.night.visit_mut(f);
+                for source in [&mut options.dawn, &mut options.dusk].into_iter().flatten() {
+                    source.visit_mut(f);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether this source, or any source nested inside it, is Unsplash.
+    pub fn uses_unsplash(&self) -> bool {
+        match self {
+            SourceConfig::Unsplash { .. } => true,
+            SourceConfig::Playlist(options) => {
+                options.entries.iter().any(|e| e.source.uses_unsplash())
+            }
+            SourceConfig::Solar(options) => {
+                options.day.uses_unsplash()
+                    || options.night.uses_unsplash()
+                    || [&options.dawn, &options.dusk]
+                        .into_iter()
+                        .flatten()
+                        .any(|source| source.uses_unsplash())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Fixed fire times, evaluated in local time. Replaces `update_interval` when set.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Schedule {
+    /// "HH:MM" times, on the days matched by a cron day-of-week field such as
+    /// `mon-fri`. An empty `days` means every day.
+    TimesOfDay {
+        times: Vec<String>,
+        #[serde(default)]
+        days: String,
+    },
+    /// Five-field cron expression (minute hour day-of-month month day-of-week)
+    /// or one of `@hourly`, `@daily`, `@weekly`, `@monthly`, `@yearly`.
+    Cron { expression: String },
+}
+
+/// What to do about a change that fell due while the app was closed or the
+/// machine was asleep.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum MissedRunPolicy {
+    #[default]
+    RunImmediately,
+    Skip,
+}
+
+/// How often scheduled rotation runs when no `schedule` is set.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum UpdateInterval {
+    #[default]
+    #[serde(rename = "manual")]
+    Manual,
+    #[serde(rename = "30min")]
+    ThirtyMinutes,
+    #[serde(rename = "1hour")]
+    Hourly,
+    #[serde(rename = "6hour")]
+    SixHours,
+    #[serde(rename = "daily")]
+    Daily,
+}
+
+impl UpdateInterval {
+    /// `None` for manual rotation.
+    pub fn period(self) -> Option<TimeDelta> {
+        match self {
+            UpdateInterval::Manual => None,
+            UpdateInterval::ThirtyMinutes => Some(TimeDelta::minutes(30)),
+            UpdateInterval::Hourly => Some(TimeDelta::hours(1)),
+            UpdateInterval::SixHours => Some(TimeDelta::hours(6)),
+            UpdateInterval::Daily => Some(TimeDelta::days(1)),
+        }
+    }
+}
+
+/// Where API keys are kept. Settings files only ever hold references to them.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum SecretStorage {
+    /// The OS keyring where one is available, otherwise `MachineKeyFile`.
+    #[default]
+    Auto,
+    Keyring,
+    /// An encrypted file whose key is derived from this machine's id.
+    MachineKeyFile,
+    /// An encrypted file that has to be unlocked with a passphrase each run.
+    PassphraseFile,
+}
+
+/// How a wallpaper is laid onto the screens.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum FitMode {
+    /// One image across all screens, cropped to cover the whole desktop.
+    #[default]
+    Span,
+    /// The image on every screen, cropped to cover it.
+    Fill,
+    /// The whole image on every screen, with black bars where it is narrower
+    /// or shorter than the screen.
+    Fit,
+    /// The whole image on every screen, scaled out of proportion to cover it.
+    Stretch,
+}
+
+/// Adjustments made to each screen's image after it is cropped.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Effects {
+    /// Gaussian blur radius in screen points; 0 leaves the image sharp.
+    pub blur: f32,
+    /// -100 (black) to 100 (white); 0 leaves the image as it is.
+    pub brightness: i32,
+    pub grayscale: bool,
+}
+
+const DEFAULT_PROFILE: &str = "Default";
+
+/// A named set of what to show and when, such as "Work" or "Weekend".
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub rotation_source: Option<RotationSource>,
+    #[serde(default)]
+    pub source: Option<SourceConfig>,
+    #[serde(default)]
+    pub update_interval: UpdateInterval,
+    #[serde(default)]
+    pub schedule: Option<Schedule>,
+    #[serde(default)]
+    pub missed_run_policy: MissedRunPolicy,
+    #[serde(default)]
+    pub fit_mode: FitMode,
+    #[serde(default)]
+    pub effects: Effects,
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Self {
+        Profile {
+            name: name.to_string(),
+            rotation_source: None,
+            source: None,
+            update_interval: UpdateInterval::Manual,
+            schedule: None,
+            missed_run_policy: MissedRunPolicy::default(),
+            fit_mode: FitMode::default(),
+            effects: Effects::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
 pub struct Settings {
+    pub version: u32,
     pub api_key: String,
     pub topic_slug: String,
-    pub update_interval: String,
+    #[serde(default)]
+    pub search_query: String,
+    #[serde(default)]
+    pub rotation_source: Option<RotationSource>,
+    #[serde(default)]
+    pub source: Option<SourceConfig>,
+    pub update_interval: UpdateInterval,
+    #[serde(default)]
+    pub schedule: Option<Schedule>,
+    #[serde(default)]
+    pub missed_run_policy: MissedRunPolicy,
+    #[serde(default)]
+    pub fit_mode: FitMode,
+    #[serde(default)]
+    pub effects: Effects,
+    #[serde(default = "default_max_download_mb")]
+    pub max_download_mb: u64,
+    #[serde(default = "default_max_image_megapixels")]
+    pub max_image_megapixels: u64,
+    #[serde(default)]
+    pub proxy_url: String,
+    #[serde(default)]
+    pub no_proxy: String,
+    #[serde(default)]
+    pub extra_root_certs: Vec<String>,
+    /// How many recent wallpapers scheduled rotation must not repeat, by id or
+    /// by near-identical image. 0 disables the check; history caps the window.
+    #[serde(default = "default_no_repeat_window")]
+    pub no_repeat_window: usize,
+    pub secret_storage: SecretStorage,
+    /// Every profile, including the active one. The top-level source and
+    /// timing fields are the active profile's and win over its entry here.
+    pub profiles: Vec<Profile>,
+    pub active_profile: String,
+}
+
+fn default_no_repeat_window() -> usize {
+    50
+}
+
+fn default_max_download_mb() -> u64 {
+    50
+}
+
+fn default_max_image_megapixels() -> u64 {
+    200
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Settings {
+            version: SETTINGS_VERSION,
             api_key: String::new(),
             topic_slug: String::new(),
-            update_interval: "manual".to_string(),
+            search_query: String::new(),
+            rotation_source: None,
+            source: None,
+            update_interval: UpdateInterval::Manual,
+            schedule: None,
+            missed_run_policy: MissedRunPolicy::default(),
+            fit_mode: FitMode::default(),
+            effects: Effects::default(),
+            max_download_mb: default_max_download_mb(),
+            max_image_megapixels: default_max_image_megapixels(),
+            proxy_url: String::new(),
+            no_proxy: String::new(),
+            extra_root_certs: Vec::new(),
+            no_repeat_window: default_no_repeat_window(),
+            secret_storage: SecretStorage::default(),
+            profiles: Vec::new(),
+            active_profile: DEFAULT_PROFILE.to_string(),
         }
     }
 }
 
-fn settings_path(app: &AppHandle) -> std::path::PathBuf {
+impl Settings {
+    pub fn find_profile(&self, name: &str) -> Option<usize> {
+        self.profiles
+            .iter()
+            .position(|p| p.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Copies the top-level fields into the active profile's entry, adding the
+    /// entry if it is missing.
+    pub fn store_active_profile(&mut self) {
+        let profile = Profile {
+            name: self.active_profile.clone(),
+            rotation_source: self.rotation_source.clone(),
+            source: self.source.clone(),
+            update_interval: self.update_interval,
+            schedule: self.schedule.clone(),
+            missed_run_policy: self.missed_run_policy,
+            fit_mode: self.fit_mode,
+            effects: self.effects,
+        };
+        match self.find_profile(&self.active_profile) {
+            Some(index) => self.profiles[index] = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+
+    /// Calls `f` on every configured source, in all profiles.
+    pub fn sources_mut(&mut self, f: &mut dyn FnMut(&mut SourceConfig)) {
+        let profiles = self.profiles.iter_mut().map(|p| &mut p.source);
+        for source in std::iter::once(&mut self.source).chain(profiles).flatten() {
+            source.visit_mut(f);
+        }
+    }
+
+    /// Makes the named profile active, keeping the current one's fields.
+    pub fn switch_profile(&mut self, name: &str) -> Result<(), String> {
+        let index = self
+            .find_profile(name)
+            .ok_or_else(|| format!("No profile named {name}"))?;
+        self.store_active_profile();
+        let profile = self.profiles[index].clone();
+        self.active_profile = profile.name;
+        self.rotation_source = profile.rotation_source;
+        self.source = profile.source;
+        self.update_interval = profile.update_interval;
+        self.schedule = profile.schedule;
+        self.missed_run_policy = profile.missed_run_policy;
+        self.fit_mode = profile.fit_mode;
+        self.effects = profile.effects;
+        Ok(())
+    }
+
+    // Day/night sources and wallpaper sets follow the clock even without an
+    // interval.
+    pub fn scheduling_enabled(&self) -> bool {
+        self.schedule.is_some()
+            || self.update_interval != UpdateInterval::Manual
+            || matches!(
+                self.effective_source(),
+                SourceConfig::Solar(_) | SourceConfig::DynamicSet(_)
+            )
+    }
+
+    // Settings saved before rotation sources existed only carry a topic slug or
+    // a search query; those map onto the equivalent source.
+    pub fn effective_rotation_source(&self) -> RotationSource {
+        if let Some(source) = &self.rotation_source {
+            return source.clone();
+        }
+        let query = self.search_query.trim();
+        if !query.is_empty() {
+            RotationSource::Search {
+                query: query.to_string(),
+            }
+        } else if !self.topic_slug.is_empty() {
+            RotationSource::Topic {
+                slug: self.topic_slug.clone(),
+            }
+        } else {
+            RotationSource::Editorial
+        }
+    }
+
+    pub fn effective_source(&self) -> SourceConfig {
+        self.source.clone().unwrap_or_else(|| SourceConfig::Unsplash {
+            rotation: self.effective_rotation_source(),
+        })
+    }
+}
+
+/// A problem with one setting. `field` is a camelCase path such as
+/// `source.entries[1].source.apiKey`.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SettingsError {
+    Invalid { errors: Vec<FieldError> },
+    /// settings.json is not valid JSON or cannot be migrated.
+    Unreadable { message: String },
+    Io { message: String },
+}
+
+struct Errors(Vec<FieldError>);
+
+impl Errors {
+    fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn require(&mut self, field: &str, value: &str, what: &str) {
+        if value.trim().is_empty() {
+            self.add(field, format!("{what} is required"));
+        }
+    }
+
+    fn url(&mut self, field: &str, value: &str) {
+        match reqwest::Url::parse(value.trim()) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(_) => self.add(field, "Only http and https URLs are supported"),
+            Err(e) => self.add(field, format!("Invalid URL: {e}")),
+        }
+    }
+
+    fn coordinates(&mut self, path: &str, latitude: f64, longitude: f64) {
+        if !(-90.0..=90.0).contains(&latitude) {
+            self.add(&format!("{path}.latitude"), "Must be between -90 and 90");
+        }
+        if !(-180.0..=180.0).contains(&longitude) {
+            self.add(&format!("{path}.longitude"), "Must be between -180 and 180");
+        }
+    }
+}
+
+fn validate_rotation(errors: &mut Errors, path: &str, rotation: &RotationSource) {
+    match rotation {
+        RotationSource::Editorial | RotationSource::Random { .. } => {}
+        RotationSource::Topic { slug } => errors.require(&format!("{path}.slug"), slug, "Topic"),
+        RotationSource::Collection { id } => {
+            errors.require(&format!("{path}.id"), id, "Collection id")
+        }
+        RotationSource::User { username } => {
+            errors.require(&format!("{path}.username"), username, "Username")
+        }
+        RotationSource::Search { query } => {
+            errors.require(&format!("{path}.query"), query, "Search query")
+        }
+    }
+}
+
+fn validate_source(errors: &mut Errors, path: &str, source: &SourceConfig) {
+    let field = |name: &str| format!("{path}.{name}");
+    match source {
+        SourceConfig::Unsplash { rotation } => {
+            validate_rotation(errors, &field("rotation"), rotation)
+        }
+        SourceConfig::Pexels { api_key, .. } => {
+            errors.require(&field("apiKey"), api_key, "Pexels API key")
+        }
+        SourceConfig::Wallhaven(options) => {
+            for (name, value) in [
+                ("categories", &options.categories),
+                ("purity", &options.purity),
+            ] {
+                if value.len() != 3 || !value.chars().all(|c| c == '0' || c == '1') {
+                    errors.add(&field(name), "Expected three 0/1 flags such as \"100\"");
+                }
+            }
+            let at_least = options.at_least.trim();
+            if !at_least.is_empty()
+                && at_least
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse::<u32>().ok()?, h.parse::<u32>().ok()?)))
+                    .is_none()
+            {
+                errors.add(&field("atLeast"), "Expected a resolution such as 2560x1440");
+            }
+        }
+        SourceConfig::JsonFeed(options) => errors.url(&field("url"), &options.url),
+        SourceConfig::Feed(options) => {
+            errors.url(&field("url"), &options.url);
+            if let (Some(min), Some(max)) = (options.min_aspect, options.max_aspect) {
+                if min > max {
+                    errors.add(
+                        &field("maxAspect"),
+                        "Must not be below the minimum aspect ratio",
+                    );
+                }
+            }
+        }
+        SourceConfig::LocalFolder(options) => {
+            if options.directories.is_empty() {
+                errors.add(&field("directories"), "Add at least one folder");
+            }
+            for (i, dir) in options.directories.iter().enumerate() {
+                if !Path::new(dir).is_dir() {
+                    errors.add(&format!("{path}.directories[{i}]"), "Folder does not exist");
+                }
+            }
+        }
+        SourceConfig::Daily(_) | SourceConfig::Favorites => {}
+        SourceConfig::Playlist(options) => {
+            if options.entries.iter().all(|e| e.weight == 0) {
+                errors.add(&field("entries"), "Add a source with a weight above zero");
+            }
+            for (i, entry) in options.entries.iter().enumerate() {
+                let entry_path = format!("{path}.entries[{i}].source");
+                if matches!(entry.source, SourceConfig::Playlist(_)) {
+                    errors.add(&entry_path, "Playlists cannot contain other playlists");
+                } else {
+                    validate_source(errors, &entry_path, &entry.source);
+                }
+            }
+        }
+        SourceConfig::Solar(options) => {
+            errors.coordinates(path, options.latitude, options.longitude);
+            validate_source(errors, &field("day"), &options.day);
+            validate_source(errors, &field("night"), &options.night);
+            if let Some(dawn) = &options.dawn {
+                validate_source(errors, &field("dawn"), dawn);
+            }
+            if let Some(dusk) = &options.dusk {
+                validate_source(errors, &field("dusk"), dusk);
+            }
+        }
+        SourceConfig::SolidColor { color } => {
+            if crate::sources::parse_color(color).is_none() {
+                errors.add(&field("color"), "Expected a colour such as #1e1e1e");
+            }
+        }
+        SourceConfig::DynamicSet(options) => {
+            errors.require(&field("setId"), &options.set_id, "Wallpaper set");
+            match (options.latitude, options.longitude) {
+                (Some(latitude), Some(longitude)) => errors.coordinates(path, latitude, longitude),
+                (None, None) => {}
+                _ => errors.add(path, "Give both latitude and longitude, or neither"),
+            }
+        }
+    }
+}
+
+fn validate_effects(errors: &mut Errors, path: &str, effects: &Effects) {
+    if !(0.0..=50.0).contains(&effects.blur) {
+        errors.add(&format!("{path}.blur"), "Must be between 0 and 50");
+    }
+    if !(-100..=100).contains(&effects.brightness) {
+        errors.add(&format!("{path}.brightness"), "Must be between -100 and 100");
+    }
+}
+
+impl Settings {
+    /// Whether the active source or any profile draws from Unsplash, which
+    /// is what needs the access key. A profile without a source uses Unsplash.
+    pub fn uses_unsplash(&self) -> bool {
+        self.effective_source().uses_unsplash()
+            || self
+                .profiles
+                .iter()
+                .any(|p| p.source.as_ref().is_none_or(SourceConfig::uses_unsplash))
+    }
+
+    /// Every problem that would stop these settings from working, by field.
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Errors(Vec::new());
+        if self.uses_unsplash() {
+            errors.require("apiKey", &self.api_key, "Unsplash access key");
+        }
+        if let Some(rotation) = &self.rotation_source {
+            validate_rotation(&mut errors, "rotationSource", rotation);
+        }
+        if let Some(source) = &self.source {
+            validate_source(&mut errors, "source", source);
+        }
+        validate_effects(&mut errors, "effects", &self.effects);
+        if let Some(schedule) = &self.schedule {
+            if let Err(e) = crate::schedule::CompiledSchedule::compile(schedule) {
+                errors.add("schedule", e);
+            }
+        }
+        for (i, profile) in self.profiles.iter().enumerate() {
+            let field = format!("profiles[{i}].name");
+            errors.require(&field, &profile.name, "Profile name");
+            if self.find_profile(&profile.name) != Some(i) {
+                errors.add(&field, format!("There is already a profile named {}", profile.name));
+            }
+            validate_effects(&mut errors, &format!("profiles[{i}].effects"), &profile.effects);
+        }
+        if self.find_profile(&self.active_profile).is_none() {
+            errors.add("activeProfile", "No profile has this name");
+        }
+        if self.max_download_mb == 0 {
+            errors.add("maxDownloadMb", "Must be at least 1");
+        }
+        if self.max_image_megapixels == 0 {
+            errors.add("maxImageMegapixels", "Must be at least 1");
+        }
+        let proxy_url = self.proxy_url.trim();
+        if !proxy_url.is_empty() {
+            if let Err(e) = reqwest::Proxy::all(proxy_url) {
+                errors.add("proxyUrl", format!("Invalid proxy URL: {e}"));
+            }
+        }
+        for (i, path) in self.extra_root_certs.iter().enumerate() {
+            if !Path::new(path).is_file() {
+                errors.add(
+                    &format!("extraRootCerts[{i}]"),
+                    "Certificate file does not exist",
+                );
+            }
+        }
+        errors.0
+    }
+}
+
+/// Top-level settings whose values differ, by their names in settings.json.
+pub fn changed_fields(old: &Settings, new: &Settings) -> Vec<String> {
+    let (Ok(Value::Object(old)), Ok(Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+    new.iter()
+        .filter(|(key, value)| old.get(*key) != Some(value))
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsChanged {
+    pub changed: Vec<String>,
+    pub settings: Settings,
+}
+
+pub fn settings_path(app: &AppHandle) -> std::path::PathBuf {
     app.path().app_data_dir().unwrap().join("settings.json")
 }
 
-pub fn load_settings_inner(app: &AppHandle) -> Settings {
-    let path = settings_path(app);
-    if path.exists() {
-        if let Ok(data) = fs::read_to_string(&path) {
-            if let Ok(settings) = serde_json::from_str::<Settings>(&data) {
-                return settings;
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+// Each migration takes a file from the version at its index to the next one.
+const MIGRATIONS: &[Migration] = &[migrate_v0, migrate_v1];
+
+// Unversioned files stored the interval as free text; anything unrecognised
+// meant no rotation.
+fn migrate_v0(settings: &mut Value) -> Result<(), String> {
+    let settings = settings
+        .as_object_mut()
+        .ok_or("settings are not a JSON object")?;
+    let interval = settings
+        .get("updateInterval")
+        .and_then(Value::as_str)
+        .map(|s| s.trim().to_ascii_lowercase());
+    let interval = match interval.as_deref() {
+        Some("30min" | "30m") => "30min",
+        Some("1hour" | "1h" | "hourly") => "1hour",
+        Some("6hour" | "6h") => "6hour",
+        Some("daily" | "1day" | "24h") => "daily",
+        _ => "manual",
+    };
+    settings.insert("updateInterval".to_string(), interval.into());
+    Ok(())
+}
+
+// Profiles arrived in v2; existing settings become the "Default" profile.
+fn migrate_v1(settings: &mut Value) -> Result<(), String> {
+    let settings = settings
+        .as_object_mut()
+        .ok_or("settings are not a JSON object")?;
+    let mut profile = serde_json::Map::new();
+    profile.insert("name".to_string(), DEFAULT_PROFILE.into());
+    for key in [
+        "rotationSource",
+        "source",
+        "updateInterval",
+        "schedule",
+        "missedRunPolicy",
+    ] {
+        if let Some(value) = settings.get(key) {
+            profile.insert(key.to_string(), value.clone());
+        }
+    }
+    settings.insert("profiles".to_string(), vec![Value::Object(profile)].into());
+    settings.insert("activeProfile".to_string(), DEFAULT_PROFILE.into());
+    Ok(())
+}
+
+/// Brings a settings document up to `SETTINGS_VERSION`. Returns whether
+/// anything changed.
+pub fn migrate(settings: &mut Value) -> Result<bool, String> {
+    let version = match settings.get("version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| format!("invalid settings version {v}"))? as u32,
+    };
+    if version > SETTINGS_VERSION {
+        return Err(format!(
+            "settings version {version} is newer than this app supports ({SETTINGS_VERSION})"
+        ));
+    }
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(settings).map_err(|e| format!("migrating settings from v{from}: {e}"))?;
+        settings["version"] = (from as u32 + 1).into();
+    }
+    Ok(version < SETTINGS_VERSION)
+}
+
+fn parse_settings(data: &str) -> Result<(Settings, bool), String> {
+    let mut value: Value = serde_json::from_str(data).map_err(|e| e.to_string())?;
+    let migrated = migrate(&mut value)?;
+    let settings = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    Ok((settings, migrated))
+}
+
+// Blanks every `"apiKey": "..."` value that is one of `stored`, the keys the
+// secret store holds. Works on the text, so files that are not valid JSON are
+// cleaned as well.
+fn scrub_api_keys(text: &str, stored: &[String]) -> String {
+    const KEY: &str = "\"apiKey\"";
+    let mut scrubbed = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find(KEY) {
+        let (before, after) = rest.split_at(at + KEY.len());
+        scrubbed.push_str(before);
+        rest = after;
+        let Some(body) = rest
+            .trim_start()
+            .strip_prefix(':')
+            .and_then(|value| value.trim_start().strip_prefix('"'))
+        else {
+            continue;
+        };
+        let mut escaped = false;
+        let Some(end) = body.find(|c| {
+            let closes = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            closes
+        }) else {
+            continue;
+        };
+        let value = &body[..end];
+        scrubbed.push_str(&rest[..rest.len() - body.len()]);
+        let unescaped: Option<String> = serde_json::from_str(&format!("\"{value}\"")).ok();
+        if !unescaped.is_some_and(|key| stored.contains(&key)) {
+            scrubbed.push_str(value);
+        }
+        rest = &body[end..];
+    }
+    scrubbed.push_str(rest);
+    scrubbed
+}
+
+// Backups of settings.json, from before the secret store or set aside because
+// they did not parse, may hold keys in plain text. Keys the secret store now
+// holds go; anything else, including keys that exist only in the backup, stays.
+fn scrub_backups(dir: &Path, stored: &[String]) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let backup = name == "settings.json.bak"
+            || (name.starts_with("settings.invalid-") && name.ends_with(".json"));
+        if !backup {
+            continue;
+        }
+        let path = entry.path();
+        let Ok(bytes) = fs::read(&path) else {
+            continue;
+        };
+        // Not text, so there is no telling where keys are; leave it be.
+        let Ok(text) = String::from_utf8(bytes) else {
+            eprintln!(
+                "[WallCraft] {} is not text and may hold API keys",
+                path.display()
+            );
+            continue;
+        };
+        let scrubbed = scrub_api_keys(&text, stored);
+        if scrubbed == text {
+            continue;
+        }
+        if let Err(e) = write_atomic(&path, scrubbed.as_bytes()) {
+            eprintln!("[WallCraft] cannot remove keys from {}: {e}", path.display());
+        }
+    }
+}
+
+// Moves an unreadable file aside rather than letting the next save overwrite it.
+fn back_up(path: &Path, reason: &str) {
+    let backup = path.with_file_name(format!(
+        "settings.invalid-{}.json",
+        chrono::Local::now().timestamp_millis()
+    ));
+    match fs::rename(path, &backup) {
+        Ok(()) => eprintln!(
+            "[WallCraft] unreadable settings ({reason}) moved to {}",
+            backup.display()
+        ),
+        Err(e) => eprintln!("[WallCraft] unreadable settings ({reason}), backup failed: {e}"),
+    }
+}
+
+/// Writes a sibling temp file and renames it over the old one, so a crash
+/// mid-write leaves either the old or the new contents, never half of each.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let temp = write_temp(path, contents)?;
+    let result = fs::rename(&temp, path);
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// The first half of `write_atomic`: writes and syncs `<path>.tmp`, which the
+/// caller renames over `path` once it is sure it wants the change.
+pub fn write_temp(path: &Path, contents: &[u8]) -> std::io::Result<std::path::PathBuf> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = std::path::PathBuf::from(temp);
+    let written = fs::File::create(&temp).and_then(|mut file| {
+        use std::io::Write;
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    match written {
+        Ok(()) => Ok(temp),
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            Err(e)
+        }
+    }
+}
+
+/// Whether `name` is a single file name that stays inside the folder it is
+/// joined to on every platform: no separators, no `.` or `..`, no drive or
+/// stream prefixes, and nothing Windows would strip or refuse.
+pub fn is_plain_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    let single = matches!(
+        (components.next(), components.next()),
+        (Some(std::path::Component::Normal(part)), None) if part == name
+    );
+    single
+        && name.trim() == name
+        && !name.ends_with('.')
+        && !name.chars().any(|c| {
+            c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+        })
+}
+
+// Secrets go to the secret store and the file only references them.
+fn write_settings(
+    home: &dyn crate::secrets::SecretHome,
+    path: &Path,
+    settings: &Settings,
+) -> Result<(), String> {
+    let settings = crate::secrets::externalize(home, settings)?;
+    let json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    write_atomic(path, json.as_bytes()).map_err(|e| format!("Cannot save settings: {e}"))
+}
+
+fn read_from_disk(app: &AppHandle) -> Settings {
+    read_settings(app, &settings_path(app))
+}
+
+fn read_settings(home: &dyn crate::secrets::SecretHome, path: &Path) -> Settings {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Settings::default(),
+        // Not text, or not readable by us: set it aside like any unparseable
+        // file, so the next save does not overwrite the keys in it.
+        Err(e) => {
+            back_up(path, &e.to_string());
+            return Settings::default();
+        }
+    };
+    // Keys that made it into the secret store, and so can go from backups.
+    let mut stored = Vec::new();
+    let settings = match parse_settings(&data) {
+        Ok((mut settings, migrated)) => {
+            // Keys from before the secret store are moved into it, and the
+            // plain-text copy is not kept as a backup.
+            let plaintext = crate::secrets::has_plaintext(&settings);
+            crate::secrets::resolve(home, &mut settings);
+            // Keep the pre-migration file around in case the new version is rolled back.
+            if migrated && !plaintext {
+                let _ = fs::copy(path, path.with_extension("json.bak"));
+            }
+            let saved = if migrated || plaintext {
+                write_settings(home, path, &settings)
+            } else {
+                Ok(())
+            };
+            match saved {
+                Ok(()) => stored = crate::secrets::stored_values(&settings),
+                Err(e) => eprintln!("[WallCraft] failed to save migrated settings: {e}"),
             }
+            settings
+        }
+        Err(e) => {
+            back_up(path, &e);
+            Settings::default()
+        }
+    };
+    if let Some(dir) = path.parent().filter(|_| !stored.is_empty()) {
+        scrub_backups(dir, &stored);
+    }
+    settings
+}
+
+/// The settings in effect. Read from disk once, then kept up to date by saves
+/// and by the file watcher.
+pub fn load_settings_inner(app: &AppHandle) -> Settings {
+    let Some(state) = app.try_state::<crate::AppState>() else {
+        return read_from_disk(app);
+    };
+    let mut current = state.settings.lock().unwrap();
+    current.get_or_insert_with(|| read_from_disk(app)).clone()
+}
+
+// Makes `settings` current and tells the scheduler and every window what changed.
+fn commit(app: &AppHandle, state: &crate::AppState, settings: Settings) {
+    let previous = state.settings.lock().unwrap().replace(settings.clone());
+    let changed = match &previous {
+        Some(previous) => changed_fields(previous, &settings),
+        None => changed_fields(&Settings::default(), &settings),
+    };
+    if changed.is_empty() {
+        return;
+    }
+    crate::scheduler::on_settings_changed(state, &settings, &changed, app.clone());
+    if changed.iter().any(|f| f == "profiles" || f == "activeProfile") {
+        if let Err(e) = crate::refresh_tray_menu(app) {
+            eprintln!("[WallCraft] failed to update tray menu: {e}");
         }
     }
-    Settings::default()
+    let _ = app.emit("settings-changed", SettingsChanged { changed, settings });
+}
+
+/// Applies settings.json after it was edited outside the app. An edit that
+/// does not parse or validate is reported and the current settings stay in
+/// effect; the file itself is left alone so it can be fixed.
+pub fn reload(app: &AppHandle) {
+    let data = match fs::read_to_string(settings_path(app)) {
+        Ok(data) => data,
+        // Deleted or mid-replace; wait for the next change.
+        Err(_) => return,
+    };
+    let mut settings = match parse_settings(&data) {
+        Ok((settings, _)) => settings,
+        Err(message) => {
+            let _ = app.emit("settings-invalid", SettingsError::Unreadable { message });
+            return;
+        }
+    };
+    // Changing activeProfile by hand switches to that profile; otherwise the
+    // top-level fields are the active profile's.
+    let current = load_settings_inner(app);
+    let switched = !settings
+        .active_profile
+        .eq_ignore_ascii_case(&current.active_profile);
+    if switched && settings.find_profile(&settings.active_profile).is_some() {
+        let name = std::mem::take(&mut settings.active_profile);
+        settings.active_profile = current.active_profile;
+        let _ = settings.switch_profile(&name);
+    } else {
+        settings.store_active_profile();
+    }
+    let errors = settings.validate();
+    if !errors.is_empty() {
+        let _ = app.emit("settings-invalid", SettingsError::Invalid { errors });
+        return;
+    }
+    // A key pasted into the file by hand is moved into the secret store.
+    if crate::secrets::has_plaintext(&settings) {
+        if let Err(e) = write_settings(app, &settings_path(app), &settings) {
+            eprintln!("[WallCraft] failed to move secrets out of settings: {e}");
+        }
+    }
+    crate::secrets::resolve(app, &mut settings);
+    commit(app, &app.state::<crate::AppState>(), settings);
 }
 
 #[tauri::command]
@@ -45,20 +1208,293 @@ pub fn get_settings(app: AppHandle) -> Settings {
 pub fn save_settings(
     app: AppHandle,
     state: tauri::State<'_, crate::AppState>,
-    settings: Settings,
-) -> Result<bool, String> {
-    let path = settings_path(&app);
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    mut settings: Settings,
+) -> Result<bool, SettingsError> {
+    // Profiles are managed by their own commands; edits here apply to the
+    // active one.
+    let current = load_settings_inner(&app);
+    settings.profiles = current.profiles;
+    settings.active_profile = current.active_profile;
+    store(&app, &state, settings)?;
+    Ok(true)
+}
+
+/// Validates, saves and applies `settings`.
+pub fn store(
+    app: &AppHandle,
+    state: &crate::AppState,
+    mut settings: Settings,
+) -> Result<(), SettingsError> {
+    settings.store_active_profile();
+    let errors = settings.validate();
+    if !errors.is_empty() {
+        return Err(SettingsError::Invalid { errors });
     }
-    let json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
-    fs::write(&path, json).map_err(|e| e.to_string())?;
+    settings.version = SETTINGS_VERSION;
+    // Make sure there is something to compare against before replacing it.
+    load_settings_inner(app);
+    write_settings(app, &settings_path(app), &settings)
+        .map_err(|message| SettingsError::Io { message })?;
+    commit(app, state, settings);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::{SecretBackend, SecretHome};
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    #[test]
+    fn switching_profiles_swaps_fit_mode_and_effects() {
+        let presentation: Profile = serde_json::from_value(serde_json::json!({
+            "name": "Presentation",
+            "source": { "provider": "solidColor", "color": "#1e1e1e" },
+            "fitMode": "fit",
+            "effects": { "brightness": -30 }
+        }))
+        .unwrap();
+        let mut settings = Settings {
+            effects: Effects {
+                blur: 4.0,
+                ..Effects::default()
+            },
+            ..Settings::default()
+        };
+        settings.profiles.push(presentation);
 
-    // Restart scheduler with new interval
-    crate::scheduler::stop_scheduler_inner(&state);
-    if settings.update_interval != "manual" {
-        crate::scheduler::start_scheduler_inner(&state, settings, app);
+        settings.switch_profile("presentation").unwrap();
+        assert_eq!(settings.fit_mode, FitMode::Fit);
+        assert_eq!(settings.effects.brightness, -30);
+        assert_eq!(settings.effects.blur, 0.0);
+        assert!(settings.validate().iter().all(|e| !e.field.starts_with("source")));
+
+        settings.switch_profile(DEFAULT_PROFILE).unwrap();
+        assert_eq!(settings.fit_mode, FitMode::Span);
+        assert_eq!(settings.effects.blur, 4.0);
     }
 
-    Ok(true)
+    #[test]
+    fn solid_colours_and_effects_are_validated() {
+        let settings = Settings {
+            source: Some(SourceConfig::SolidColor {
+                color: "dark".to_string(),
+            }),
+            effects: Effects {
+                blur: -1.0,
+                brightness: 101,
+                grayscale: false,
+            },
+            ..Settings::default()
+        };
+        let fields: Vec<String> = settings.validate().into_iter().map(|e| e.field).collect();
+        for field in ["source.color", "effects.blur", "effects.brightness"] {
+            assert!(fields.iter().any(|f| f == field), "{field} in {fields:?}");
+        }
+    }
+
+    fn unsplash_free() -> SourceConfig {
+        SourceConfig::Daily(DailyFeed::Bing {
+            market: "en-US".to_string(),
+        })
+    }
+
+    #[test]
+    fn access_key_is_only_required_for_unsplash() {
+        let key_error =
+            |settings: &Settings| settings.validate().iter().any(|e| e.field == "apiKey");
+        let mut settings = Settings::default();
+        assert!(settings.api_key.is_empty());
+        assert!(key_error(&settings));
+
+        settings.source = Some(unsplash_free());
+        let mut home = Profile::new("Home");
+        home.source = Some(unsplash_free());
+        settings.profiles.push(home);
+        assert!(!key_error(&settings));
+
+        // Nested in a day/night set.
+        let mut solar: SolarOptions = serde_json::from_value(serde_json::json!({
+            "latitude": 0.0,
+            "longitude": 0.0,
+            "day": { "provider": "favorites" },
+            "night": { "provider": "favorites" }
+        }))
+        .unwrap();
+        solar.dusk = Some(Box::new(SourceConfig::Unsplash {
+            rotation: RotationSource::Editorial,
+        }));
+        settings.source = Some(SourceConfig::Solar(solar));
+        assert!(key_error(&settings));
+
+        // Another profile left on the default source.
+        settings.source = Some(unsplash_free());
+        settings.profiles.push(Profile::new("Work"));
+        assert!(key_error(&settings));
+    }
+
+    #[test]
+    fn backups_lose_their_keys() {
+        let json = r#"{
+  "apiKey": "unsplash-key",
+  "profiles": [{ "source": { "provider": "pexels", "apiKey" : "pexels \"key\"" } }],
+  "source": { "provider": "daily", "feed": "nasaApod", "apiKey": "DEMO_KEY" },
+  "other": { "apiKey": "secret:0f3a" }
+}"#;
+        let stored = ["unsplash-key".to_string(), "pexels \"key\"".to_string()];
+        let scrubbed = scrub_api_keys(json, &stored);
+        assert!(!scrubbed.contains("unsplash-key") && !scrubbed.contains("pexels \\\""));
+        let value: Value = serde_json::from_str(&scrubbed).unwrap();
+        assert_eq!(value["apiKey"], "");
+        assert_eq!(value["profiles"][0]["source"]["apiKey"], "");
+        assert_eq!(value["source"]["apiKey"], "DEMO_KEY");
+        assert_eq!(value["other"]["apiKey"], "secret:0f3a");
+
+        // Keys the store does not hold are left alone.
+        assert_eq!(scrub_api_keys(json, &[]), json);
+
+        // Files set aside for not parsing are cleaned too.
+        let broken = r#"{ "apiKey": "abc", "updateInterval": "#;
+        let stored = ["abc".to_string()];
+        assert_eq!(
+            scrub_api_keys(broken, &stored),
+            r#"{ "apiKey": "", "updateInterval": "#
+        );
+        assert_eq!(
+            scrub_api_keys(r#"{ "apiKey": "abc"#, &stored),
+            r#"{ "apiKey": "abc"#
+        );
+    }
+
+    // Secrets kept in memory, with the index next to the settings under test.
+    struct TestHome {
+        dir: std::path::PathBuf,
+        backend: Arc<MemoryBackend>,
+    }
+
+    #[derive(Default)]
+    struct MemoryBackend(Mutex<HashMap<String, String>>);
+
+    impl SecretBackend for MemoryBackend {
+        fn get(&self, id: &str) -> Result<Option<String>, String> {
+            Ok(self.0.lock().unwrap().get(id).cloned())
+        }
+
+        fn set(&self, id: &str, value: &str) -> Result<(), String> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(id.to_string(), value.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, id: &str) -> Result<(), String> {
+            self.0.lock().unwrap().remove(id);
+            Ok(())
+        }
+    }
+
+    impl SecretHome for TestHome {
+        fn dir(&self) -> std::path::PathBuf {
+            self.dir.clone()
+        }
+
+        fn open(
+            &self,
+            _storage: SecretStorage,
+        ) -> Result<(SecretStorage, Arc<dyn SecretBackend>), String> {
+            Ok((SecretStorage::MachineKeyFile, self.backend.clone()))
+        }
+
+        fn report(&self, error: &str) {
+            panic!("unexpected secret store error: {error}");
+        }
+    }
+
+    #[test]
+    fn plaintext_keys_move_into_the_secret_store() {
+        let dir = std::env::temp_dir().join(format!("wallcraft-secrets-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let json = serde_json::json!({
+            "version": SETTINGS_VERSION,
+            "apiKey": "unsplash-key",
+            "source": {
+                "provider": "playlist",
+                "entries": [
+                    { "weight": 1, "source": { "provider": "pexels", "apiKey": "pexels-key" } }
+                ]
+            },
+            "profiles": [{
+                "name": "Work",
+                "updateInterval": "manual",
+                "source": {
+                    "provider": "solar",
+                    "latitude": 0.0,
+                    "longitude": 0.0,
+                    "day": { "provider": "daily", "feed": "nasaApod", "apiKey": "nasa-key" },
+                    "night": { "provider": "favorites" }
+                }
+            }]
+        })
+        .to_string();
+        let path = dir.join("settings.json");
+        fs::write(&path, &json).unwrap();
+        // A backup from an earlier migration, with the same keys.
+        fs::write(dir.join("settings.json.bak"), &json).unwrap();
+        // Set aside for not parsing, with a key the store never saw.
+        let invalid = r#"{ "apiKey": "only-copy-key", "#;
+        fs::write(dir.join("settings.invalid-1.json"), invalid).unwrap();
+
+        let home = TestHome {
+            dir: dir.clone(),
+            backend: Default::default(),
+        };
+        let settings = read_settings(&home, &path);
+        assert_eq!(settings.api_key, "unsplash-key");
+        let SourceConfig::Playlist(playlist) = settings.source.as_ref().unwrap() else {
+            panic!("expected the playlist");
+        };
+        assert!(matches!(
+            &playlist.entries[0].source,
+            SourceConfig::Pexels { api_key, .. } if api_key == "pexels-key"
+        ));
+
+        let mut stored: Vec<String> = home.backend.0.lock().unwrap().values().cloned().collect();
+        stored.sort();
+        assert_eq!(stored, ["nasa-key", "pexels-key", "unsplash-key"]);
+        let on_disk = fs::read_to_string(&path).unwrap();
+        let backup = fs::read_to_string(dir.join("settings.json.bak")).unwrap();
+        for key in ["unsplash-key", "pexels-key", "nasa-key"] {
+            assert!(!on_disk.contains(key), "{key} left in settings.json");
+            assert!(!backup.contains(key), "{key} left in settings.json.bak");
+        }
+        assert_eq!(on_disk.matches("secret:").count(), 3);
+        assert_eq!(
+            fs::read_to_string(dir.join("settings.invalid-1.json")).unwrap(),
+            invalid
+        );
+
+        // Reading again resolves the references from the store.
+        let again = read_settings(&home, &path);
+        assert_eq!(again.api_key, "unsplash-key");
+        assert_eq!(fs::read_to_string(&path).unwrap(), on_disk);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn plain_file_names() {
+        for name in ["frame-01.jpg", "a b.png", ".hidden", "photo..jpg"] {
+            assert!(is_plain_file_name(name), "{name} should be allowed");
+        }
+        for name in [
+            "", ".", "..", "../x", "a/b", "a/", "./a", "/etc", "..\\x", "C:x", "a:stream",
+            " a", "a ", "a.", "a\nb", "a|b",
+        ] {
+            assert!(!is_plain_file_name(name), "{name:?} should be rejected");
+        }
+    }
 }
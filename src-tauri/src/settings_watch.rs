@@ -0,0 +1,50 @@
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::{fs, time::Duration};
+use tauri::AppHandle;
+
+// Editors save in several steps (truncate, write, rename), so the file is read
+// once it has been quiet for this long.
+const SETTLE_MS: u64 = 300;
+
+/// Reloads settings.json whenever it changes on disk. Watches the directory
+/// rather than the file, because editors and our own atomic saves replace the
+/// file instead of modifying it. Dropping the watcher stops reloading.
+pub fn watch(app: &AppHandle) -> Result<RecommendedWatcher, String> {
+    let path = crate::settings::settings_path(app);
+    let dir = path
+        .parent()
+        .ok_or("Settings file has no parent directory")?
+        .to_path_buf();
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let name = path.file_name().map(|n| n.to_os_string());
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if !event.kind.is_access()
+                && event.paths.iter().any(|p| p.file_name() == name.as_deref())
+            {
+                let _ = tx.send(());
+            }
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Cannot watch {}: {e}", dir.display()))?;
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while rx.recv().await.is_some() {
+            loop {
+                match tokio::time::timeout(Duration::from_millis(SETTLE_MS), rx.recv()).await {
+                    Ok(Some(())) => continue,
+                    Ok(None) => return,
+                    Err(_) => break,
+                }
+            }
+            crate::settings::reload(&app);
+        }
+    });
+    Ok(watcher)
+}
@@ -0,0 +1,283 @@
+use chrono::{DateTime, Duration, Local, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{SolarOptions, SourceConfig};
+
+// Sun elevation at sunrise and sunset: the disc's radius plus refraction.
+const HORIZON_DEG: f64 = -0.833;
+// Civil twilight ends when the sun is 6° below the horizon.
+const CIVIL_TWILIGHT_DEG: f64 = -6.0;
+const SEARCH_STEP_MINUTES: i64 = 10;
+// Covers a polar night; a phase lasting longer than this never ends.
+const SEARCH_DAYS: i64 = 190;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SolarPhase {
+    Night,
+    /// Civil twilight before sunrise.
+    Dawn,
+    Day,
+    /// Civil twilight after sunset.
+    Dusk,
+}
+
+/// Sun elevation and hour angle in degrees, after NOAA's solar position
+/// equations. Accurate to well under a minute for switching purposes.
+pub fn position(at: DateTime<Utc>, latitude: f64, longitude: f64) -> (f64, f64) {
+    let julian_day = at.timestamp() as f64 / 86_400.0 + 2_440_587.5;
+    let jc = (julian_day - 2_451_545.0) / 36_525.0;
+
+    let mean_long = (280.46646 + jc * (36000.76983 + jc * 0.0003032)).rem_euclid(360.0);
+    let mean_anom = 357.52911 + jc * (35999.05029 - 0.0001537 * jc);
+    let eccentricity = 0.016708634 - jc * (0.000042037 + 0.0000001267 * jc);
+    let m = mean_anom.to_radians();
+    let center = m.sin() * (1.914602 - jc * (0.004817 + 0.000014 * jc))
+        + (2.0 * m).sin() * (0.019993 - 0.000101 * jc)
+        + (3.0 * m).sin() * 0.000289;
+    let omega = (125.04 - 1934.136 * jc).to_radians();
+    let apparent_long = mean_long + center - 0.00569 - 0.00478 * omega.sin();
+    let mean_obliquity =
+        23.0 + (26.0 + (21.448 - jc * (46.815 + jc * (0.00059 - jc * 0.001813))) / 60.0) / 60.0;
+    let obliquity = (mean_obliquity + 0.00256 * omega.cos()).to_radians();
+    let declination = (obliquity.sin() * apparent_long.to_radians().sin()).asin();
+
+    let y = (obliquity / 2.0).tan().powi(2);
+    let l = mean_long.to_radians();
+    let equation_of_time = 4.0
+        * (y * (2.0 * l).sin() - 2.0 * eccentricity * m.sin()
+            + 4.0 * eccentricity * y * m.sin() * (2.0 * l).cos()
+            - 0.5 * y * y * (4.0 * l).sin()
+            - 1.25 * eccentricity * eccentricity * (2.0 * m).sin())
+        .to_degrees();
+
+    let minutes = at.num_seconds_from_midnight() as f64 / 60.0;
+    let true_solar_time = (minutes + equation_of_time + 4.0 * longitude).rem_euclid(1440.0);
+    let hour_angle = true_solar_time / 4.0 - 180.0;
+
+    let lat = latitude.to_radians();
+    let cos_zenith = lat.sin() * declination.sin()
+        + lat.cos() * declination.cos() * hour_angle.to_radians().cos();
+    let elevation = 90.0 - cos_zenith.clamp(-1.0, 1.0).acos().to_degrees();
+    (elevation, hour_angle)
+}
+
+/// Without `twilight`, dawn and dusk count as night and only sunrise and
+/// sunset are transitions.
+pub fn phase_at(at: DateTime<Utc>, latitude: f64, longitude: f64, twilight: bool) -> SolarPhase {
+    let (elevation, hour_angle) = position(at, latitude, longitude);
+    if elevation >= HORIZON_DEG {
+        SolarPhase::Day
+    } else if elevation >= CIVIL_TWILIGHT_DEG && twilight {
+        if hour_angle < 0.0 {
+            SolarPhase::Dawn
+        } else {
+            SolarPhase::Dusk
+        }
+    } else {
+        SolarPhase::Night
+    }
+}
+
+/// The next phase change after `after` and the phase it starts, to within a
+/// second. Steps forward and then bisects, so polar days and nights just
+/// produce a late (or no) answer instead of an error.
+pub fn next_transition(
+    after: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+    twilight: bool,
+) -> Option<(DateTime<Utc>, SolarPhase)> {
+    let phase = |t| phase_at(t, latitude, longitude, twilight);
+    let current = phase(after);
+    let step = Duration::minutes(SEARCH_STEP_MINUTES);
+    let mut low = after;
+    while low - after < Duration::days(SEARCH_DAYS) {
+        let high = low + step;
+        if phase(high) != current {
+            let mut high = high;
+            while high - low > Duration::seconds(1) {
+                let mid = low + (high - low) / 2;
+                if phase(mid) == current {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            return Some((high, phase(high)));
+        }
+        low = high;
+    }
+    None
+}
+
+pub fn validate(options: &SolarOptions) -> Result<(), String> {
+    if !(-90.0..=90.0).contains(&options.latitude) {
+        return Err(format!("Latitude {} is outside -90..90", options.latitude));
+    }
+    if !(-180.0..=180.0).contains(&options.longitude) {
+        return Err(format!(
+            "Longitude {} is outside -180..180",
+            options.longitude
+        ));
+    }
+    Ok(())
+}
+
+/// Dawn and dusk fall back to the night source when they have none of their own.
+pub fn source_for(options: &SolarOptions, phase: SolarPhase) -> &SourceConfig {
+    match phase {
+        SolarPhase::Day => &options.day,
+        SolarPhase::Night => &options.night,
+        SolarPhase::Dawn => options.dawn.as_deref().unwrap_or(&options.night),
+        SolarPhase::Dusk => options.dusk.as_deref().unwrap_or(&options.night),
+    }
+}
+
+pub fn current_source(options: &SolarOptions) -> Result<SourceConfig, String> {
+    validate(options)?;
+    let phase = phase_at(
+        Utc::now(),
+        options.latitude,
+        options.longitude,
+        options.uses_twilight(),
+    );
+    Ok(source_for(options, phase).clone())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolarEvent {
+    /// RFC 3339, local time.
+    pub at: String,
+    pub phase: SolarPhase,
+}
+
+/// Upcoming phase changes for a location, so the settings screen can show when
+/// day and night switching will happen.
+#[tauri::command]
+pub fn upcoming_solar_events(
+    latitude: f64,
+    longitude: f64,
+    twilight: bool,
+    count: Option<usize>,
+) -> Vec<SolarEvent> {
+    let mut events = Vec::new();
+    let mut cursor = Utc::now();
+    while events.len() < count.unwrap_or(4) {
+        let Some((at, phase)) = next_transition(cursor, latitude, longitude, twilight) else {
+            break;
+        };
+        events.push(SolarEvent {
+            at: at.with_timezone(&Local).to_rfc3339(),
+            phase,
+        });
+        cursor = at;
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const LONDON: (f64, f64) = (51.5074, -0.1278);
+    const TROMSO: (f64, f64) = (69.6492, 18.9553);
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    fn transitions(
+        from: DateTime<Utc>,
+        (latitude, longitude): (f64, f64),
+        twilight: bool,
+        count: usize,
+    ) -> Vec<(DateTime<Utc>, SolarPhase)> {
+        let mut events = Vec::new();
+        let mut cursor = from;
+        while events.len() < count {
+            let Some(event) = next_transition(cursor, latitude, longitude, twilight) else {
+                break;
+            };
+            events.push(event);
+            cursor = event.0;
+        }
+        events
+    }
+
+    fn assert_near(actual: DateTime<Utc>, expected: DateTime<Utc>) {
+        let off = (actual - expected).num_seconds().abs();
+        assert!(off <= 120, "{actual} is {off}s from {expected}");
+    }
+
+    #[test]
+    fn sunrise_and_sunset_in_london() {
+        // Midsummer: 04:43 and 21:21 BST.
+        let events = transitions(utc(2024, 6, 21, 0, 0), LONDON, false, 2);
+        assert_eq!(events[0].1, SolarPhase::Day);
+        assert_near(events[0].0, utc(2024, 6, 21, 3, 43));
+        assert_eq!(events[1].1, SolarPhase::Night);
+        assert_near(events[1].0, utc(2024, 6, 21, 20, 21));
+
+        // Midwinter: 08:04 and 15:53 GMT.
+        let events = transitions(utc(2024, 12, 21, 0, 0), LONDON, false, 2);
+        assert_near(events[0].0, utc(2024, 12, 21, 8, 4));
+        assert_near(events[1].0, utc(2024, 12, 21, 15, 53));
+
+        let (latitude, longitude) = LONDON;
+        assert_eq!(
+            phase_at(utc(2024, 12, 21, 12, 0), latitude, longitude, true),
+            SolarPhase::Day
+        );
+        assert_eq!(
+            phase_at(utc(2024, 12, 21, 0, 0), latitude, longitude, true),
+            SolarPhase::Night
+        );
+    }
+
+    #[test]
+    fn twilight_comes_between_night_and_day() {
+        let events = transitions(utc(2024, 3, 20, 0, 0), LONDON, true, 8);
+        let phases: Vec<SolarPhase> = events.iter().map(|(_, phase)| *phase).collect();
+        use SolarPhase::*;
+        assert_eq!(phases, [Dawn, Day, Dusk, Night, Dawn, Day, Dusk, Night]);
+        assert!(events.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        // Civil twilight lasts about half an hour at the equinox.
+        let dawn = events[1].0 - events[0].0;
+        assert!((25..45).contains(&dawn.num_minutes()), "{dawn}");
+
+        // Without twilight, the same evening goes straight to night at sunset.
+        let plain = transitions(utc(2024, 3, 20, 0, 0), LONDON, false, 2);
+        assert_eq!(plain[1].1, Night);
+        assert_eq!(plain[1].0, events[2].0);
+    }
+
+    #[test]
+    fn polar_night_ends_late_rather_than_failing() {
+        let (latitude, longitude) = TROMSO;
+        let midwinter = utc(2024, 12, 10, 11, 0);
+        assert_eq!(
+            phase_at(midwinter, latitude, longitude, false),
+            SolarPhase::Night
+        );
+
+        // The sun is next up in the middle of January.
+        let (sunrise, phase) = next_transition(midwinter, latitude, longitude, false).unwrap();
+        assert_eq!(phase, SolarPhase::Day);
+        assert!(sunrise > utc(2025, 1, 10, 0, 0) && sunrise < utc(2025, 1, 20, 0, 0));
+
+        // Twilight still comes around every day, though.
+        let (dawn, phase) =
+            next_transition(utc(2024, 12, 10, 0, 0), latitude, longitude, true).unwrap();
+        assert_eq!(phase, SolarPhase::Dawn);
+        assert_eq!(dawn.date_naive(), midwinter.date_naive());
+
+        // Half a year of daylight at the pole is found, however late.
+        let (sunset, phase) = next_transition(utc(2024, 6, 21, 0, 0), 90.0, 0.0, false).unwrap();
+        assert_eq!(phase, SolarPhase::Night);
+        assert!(sunset > utc(2024, 9, 15, 0, 0) && sunset < utc(2024, 10, 5, 0, 0));
+    }
+}
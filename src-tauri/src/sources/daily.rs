@@ -0,0 +1,390 @@
+use async_trait::async_trait;
+use chrono::{Days, Local};
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{Attribution, Candidate, WallpaperSource};
+use crate::settings::DailyFeed;
+
+const BING_BASE: &str = "https://www.bing.com";
+const APOD_URL: &str = "https://api.nasa.gov/planetary/apod";
+const WIKIMEDIA_FEED_URL: &str = "https://api.wikimedia.org/feed/v1/wikipedia";
+
+// Each feed is a thin fetch plus a pure parser over the raw response body, so the
+// parsers can be exercised against recorded responses without network access.
+
+#[derive(Deserialize)]
+struct BingArchive {
+    images: Vec<BingImage>,
+}
+
+#[derive(Deserialize)]
+struct BingImage {
+    startdate: String,
+    urlbase: String,
+    #[serde(default)]
+    copyright: String,
+    #[serde(default)]
+    copyrightlink: Option<String>,
+}
+
+pub fn parse_bing(body: &str) -> Result<Candidate, String> {
+    let archive: BingArchive = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let image = archive
+        .images
+        .into_iter()
+        .next()
+        .ok_or_else(|| "Bing returned no image of the day".to_string())?;
+
+    // The copyright line reads "Description (© Photographer/Agency)".
+    let credit = image
+        .copyright
+        .rsplit_once('(')
+        .map(|(_, c)| c.trim_end_matches(')').trim().to_string())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| "Microsoft Bing".to_string());
+
+    Ok(Candidate {
+        source: "bing".to_string(),
+        id: format!("bing-{}", image.startdate),
+        width: 3840,
+        height: 2160,
+        preview_url: format!("{BING_BASE}{}_1920x1080.jpg", image.urlbase),
+        download_url: format!("{BING_BASE}{}_UHD.jpg", image.urlbase),
+        tracking_url: None,
+        attribution: Attribution {
+            author: credit.clone(),
+            author_url: None,
+            provider: "Bing".to_string(),
+            page_url: image.copyrightlink.filter(|l| !l.is_empty()),
+            license: None,
+        },
+    })
+}
+
+#[derive(Deserialize)]
+struct ApodEntry {
+    date: String,
+    media_type: String,
+    url: String,
+    #[serde(default)]
+    hdurl: Option<String>,
+    #[serde(default)]
+    copyright: Option<String>,
+}
+
+pub fn parse_apod(body: &str) -> Result<Candidate, String> {
+    let entry: ApodEntry = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    if entry.media_type != "image" {
+        return Err(format!(
+            "APOD for {} is a {}, not an image",
+            entry.date, entry.media_type
+        ));
+    }
+    let author = entry
+        .copyright
+        .map(|c| c.trim().replace('\n', " "))
+        .filter(|c| !c.is_empty());
+    Ok(Candidate {
+        source: "nasaApod".to_string(),
+        id: format!("apod-{}", entry.date),
+        // APOD does not report dimensions.
+        width: 0,
+        height: 0,
+        preview_url: entry.url.clone(),
+        download_url: entry.hdurl.unwrap_or(entry.url),
+        tracking_url: None,
+        attribution: Attribution {
+            author: author.clone().unwrap_or_else(|| "NASA".to_string()),
+            author_url: None,
+            provider: "NASA Astronomy Picture of the Day".to_string(),
+            page_url: Some(apod_page_url(&entry.date)),
+            // Uncredited APOD images are NASA works in the public domain.
+            license: Some(if author.is_some() {
+                "© the credited author".to_string()
+            } else {
+                "Public domain".to_string()
+            }),
+        },
+    })
+}
+
+// Today's picture is requested without a date: APOD dates are US Eastern, and a
+// local date that is already tomorrow there is rejected with HTTP 400.
+fn apod_query(api_key: &str, days_ago: u32) -> Vec<(&'static str, String)> {
+    let mut query = vec![("api_key", api_key.to_string())];
+    if days_ago > 0 {
+        let date = Local::now().date_naive() - Days::new(days_ago as u64);
+        query.push(("date", date.format("%Y-%m-%d").to_string()));
+    }
+    query
+}
+
+fn apod_page_url(date: &str) -> String {
+    // Pages are named apYYMMDD.html.
+    let compact: String = date.chars().filter(char::is_ascii_digit).collect();
+    let short = compact.get(2..).unwrap_or(&compact);
+    format!("https://apod.nasa.gov/apod/ap{short}.html")
+}
+
+#[derive(Deserialize)]
+struct WikimediaFeatured {
+    image: Option<WikimediaImage>,
+}
+
+#[derive(Deserialize)]
+struct WikimediaImage {
+    title: String,
+    image: WikimediaFile,
+    thumbnail: WikimediaFile,
+    file_page: String,
+    #[serde(default)]
+    artist: Option<WikimediaText>,
+    #[serde(default)]
+    license: Option<WikimediaLicense>,
+}
+
+#[derive(Deserialize)]
+struct WikimediaFile {
+    source: String,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Deserialize)]
+struct WikimediaText {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WikimediaLicense {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+pub fn parse_wikimedia(body: &str) -> Result<Candidate, String> {
+    let featured: WikimediaFeatured = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let image = featured
+        .image
+        .ok_or_else(|| "Wikimedia has no picture of the day".to_string())?;
+    let author = image
+        .artist
+        .and_then(|a| a.text)
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .unwrap_or_else(|| "Wikimedia Commons".to_string());
+    Ok(Candidate {
+        source: "wikimedia".to_string(),
+        id: image.title,
+        width: image.image.width,
+        height: image.image.height,
+        preview_url: image.thumbnail.source,
+        download_url: image.image.source,
+        tracking_url: None,
+        attribution: Attribution {
+            author,
+            author_url: None,
+            provider: "Wikimedia Commons".to_string(),
+            page_url: Some(image.file_page),
+            license: image.license.map(|l| match l.url {
+                Some(url) => format!("{} ({url})", l.kind),
+                None => l.kind,
+            }),
+        },
+    })
+}
+
+pub struct DailySource {
+    http: Client,
+    feed: DailyFeed,
+}
+
+impl DailySource {
+    pub fn new(http: Client, feed: DailyFeed) -> Self {
+        DailySource { http, feed }
+    }
+
+    async fn fetch_text(&self, url: &str, query: &[(&str, String)]) -> Result<String, String> {
+        let response = self
+            .http
+            .get(url)
+            .query(query)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Daily feed request failed: HTTP {}",
+                response.status()
+            ));
+        }
+        response.text().await.map_err(|e| e.to_string())
+    }
+
+    async fn fetch(&self, days_ago: u32) -> Result<Candidate, String> {
+        let date = Local::now().date_naive() - Days::new(days_ago as u64);
+        match &self.feed {
+            DailyFeed::Bing { market } => {
+                let url = format!("{BING_BASE}/HPImageArchive.aspx");
+                let query = [
+                    ("format", "js".to_string()),
+                    ("idx", days_ago.to_string()),
+                    ("n", "1".to_string()),
+                    ("mkt", market.clone()),
+                ];
+                parse_bing(&self.fetch_text(&url, &query).await?)
+            }
+            DailyFeed::NasaApod { api_key } => {
+                let query = apod_query(api_key, days_ago);
+                parse_apod(&self.fetch_text(APOD_URL, &query).await?)
+            }
+            DailyFeed::Wikimedia { language } => {
+                let url = format!(
+                    "{WIKIMEDIA_FEED_URL}/{language}/featured/{}",
+                    date.format("%Y/%m/%d")
+                );
+                parse_wikimedia(&self.fetch_text(&url, &[]).await?)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl WallpaperSource for DailySource {
+    fn key(&self) -> &'static str {
+        "daily"
+    }
+
+    // Page N is the picture from N-1 days ago, which lets the gallery browse back.
+    async fn list(&self, page: u32) -> Result<Vec<Candidate>, String> {
+        let days_ago = page.max(1) - 1;
+        // Bing only keeps the last eight days.
+        if matches!(self.feed, DailyFeed::Bing { .. }) && days_ago > 7 {
+            return Ok(Vec::new());
+        }
+        Ok(vec![self.fetch(days_ago).await?])
+    }
+
+    async fn search(&self, _query: &str, _page: u32) -> Result<Vec<Candidate>, String> {
+        Ok(Vec::new())
+    }
+
+    async fn random(&self) -> Result<Candidate, String> {
+        self.fetch(0).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/daily/",
+                $name
+            ))
+        };
+    }
+
+    #[test]
+    fn parses_bing() {
+        let candidate = parse_bing(fixture!("bing.json")).unwrap();
+        assert_eq!(candidate.id, "bing-20240312");
+        assert_eq!(
+            candidate.download_url,
+            "https://www.bing.com/th?id=OHR.SnowyOwl_EN-US1234567890_UHD.jpg"
+        );
+        assert_eq!(
+            candidate.preview_url,
+            "https://www.bing.com/th?id=OHR.SnowyOwl_EN-US1234567890_1920x1080.jpg"
+        );
+        assert_eq!(candidate.attribution.author, "© Jim Cumming/Getty Images");
+        assert_eq!(
+            candidate.attribution.page_url.as_deref(),
+            Some("https://www.bing.com/search?q=snowy+owl&form=hpcapt")
+        );
+
+        let error = parse_bing(fixture!("bing_empty.json")).unwrap_err();
+        assert_eq!(error, "Bing returned no image of the day");
+    }
+
+    #[test]
+    fn parses_apod() {
+        let candidate = parse_apod(fixture!("apod_image.json")).unwrap();
+        assert_eq!(candidate.id, "apod-2024-03-12");
+        assert_eq!(
+            candidate.download_url,
+            "https://apod.nasa.gov/apod/image/2403/M77_Hubble_4000.jpg"
+        );
+        assert_eq!(
+            candidate.preview_url,
+            "https://apod.nasa.gov/apod/image/2403/M77_Hubble_960.jpg"
+        );
+        assert_eq!(candidate.attribution.author, "John Smith");
+        assert_eq!(
+            candidate.attribution.page_url.as_deref(),
+            Some("https://apod.nasa.gov/apod/ap240312.html")
+        );
+        assert_eq!(
+            candidate.attribution.license.as_deref(),
+            Some("© the credited author")
+        );
+    }
+
+    #[test]
+    fn uncredited_apod_is_public_domain_and_falls_back_to_url() {
+        let candidate = parse_apod(fixture!("apod_public_domain.json")).unwrap();
+        assert_eq!(candidate.attribution.author, "NASA");
+        assert_eq!(
+            candidate.attribution.license.as_deref(),
+            Some("Public domain")
+        );
+        assert_eq!(candidate.download_url, candidate.preview_url);
+    }
+
+    #[test]
+    fn apod_video_days_have_no_image() {
+        let error = parse_apod(fixture!("apod_video.json")).unwrap_err();
+        assert_eq!(error, "APOD for 2024-03-11 is a video, not an image");
+    }
+
+    #[test]
+    fn apod_today_is_requested_without_a_date() {
+        assert_eq!(apod_query("key", 0), [("api_key", "key".to_string())]);
+        let query = apod_query("key", 3);
+        let expected = (Local::now().date_naive() - Days::new(3)).format("%Y-%m-%d");
+        assert_eq!(query[1], ("date", expected.to_string()));
+    }
+
+    #[test]
+    fn parses_wikimedia() {
+        let candidate = parse_wikimedia(fixture!("wikimedia.json")).unwrap();
+        assert_eq!(candidate.id, "File:Lake Bled from the Mountain.jpg");
+        assert_eq!((candidate.width, candidate.height), (6000, 4000));
+        assert_eq!(
+            candidate.download_url,
+            "https://upload.wikimedia.org/wikipedia/commons/a/a1/Lake_Bled.jpg"
+        );
+        assert!(candidate.preview_url.contains("640px-"));
+        assert_eq!(candidate.attribution.author, "Jane Doe");
+        assert_eq!(
+            candidate.attribution.license.as_deref(),
+            Some("CC BY-SA 4.0 (https://creativecommons.org/licenses/by-sa/4.0)")
+        );
+
+        let error = parse_wikimedia(fixture!("wikimedia_no_image.json")).unwrap_err();
+        assert_eq!(error, "Wikimedia has no picture of the day");
+    }
+
+    #[test]
+    fn rejects_malformed_bodies() {
+        assert!(parse_bing("<html>").is_err());
+        assert!(parse_apod(r#"{"date":"2024-03-12"}"#).is_err());
+        assert!(parse_wikimedia("[]").is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use async_trait::async_trait;
+use rand::seq::SliceRandom;
+use tauri::AppHandle;
+
+use super::{Candidate, DownloadTarget, WallpaperSource};
+use crate::favorites::{self, Favorite};
+
+const PAGE_SIZE: usize = 50;
+
+// Rotates through starred wallpapers only. Candidates keep their original
+// source and id so bans and history still recognise them.
+pub struct FavoritesSource {
+    app: AppHandle,
+}
+
+impl FavoritesSource {
+    pub fn new(app: AppHandle) -> Self {
+        FavoritesSource { app }
+    }
+
+    fn page(&self, favorites: Vec<Favorite>, page: u32) -> Vec<Candidate> {
+        let start = (page.max(1) as usize - 1) * PAGE_SIZE;
+        favorites
+            .into_iter()
+            .skip(start)
+            .take(PAGE_SIZE)
+            .map(|f| f.candidate)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl WallpaperSource for FavoritesSource {
+    fn key(&self) -> &'static str {
+        "favorites"
+    }
+
+    async fn list(&self, page: u32) -> Result<Vec<Candidate>, String> {
+        Ok(self.page(favorites::load(&self.app), page))
+    }
+
+    async fn search(&self, query: &str, page: u32) -> Result<Vec<Candidate>, String> {
+        let query = query.to_lowercase();
+        let matching = favorites::load(&self.app)
+            .into_iter()
+            .filter(|f| {
+                f.candidate
+                    .attribution
+                    .author
+                    .to_lowercase()
+                    .contains(&query)
+            })
+            .collect();
+        Ok(self.page(matching, page))
+    }
+
+    async fn random(&self) -> Result<Candidate, String> {
+        favorites::load(&self.app)
+            .choose(&mut rand::thread_rng())
+            .map(|f| f.candidate.clone())
+            .ok_or_else(|| "No favorites yet".to_string())
+    }
+
+    fn pool_size(&self) -> Option<usize> {
+        Some(favorites::load(&self.app).len())
+    }
+
+    async fn resolve_download(&self, candidate: &Candidate) -> Result<DownloadTarget, String> {
+        favorites::load(&self.app)
+            .into_iter()
+            .find(|f| f.candidate.source == candidate.source && f.candidate.id == candidate.id)
+            .map(|f| DownloadTarget::File(favorites::favorites_dir(&self.app).join(f.file)))
+            .ok_or_else(|| "Favorite no longer exists".to_string())
+    }
+}
@@ -0,0 +1,530 @@
+use async_trait::async_trait;
+use rand::seq::SliceRandom;
+use reqwest::Client;
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::PathBuf,
+};
+
+use super::{Attribution, Candidate, WallpaperSource};
+use crate::settings::FeedOptions;
+
+const IMAGE_EXTENSIONS: &[&str] = &[".jpg", ".jpeg", ".png", ".webp"];
+// Per-feed memory of applied items; old entries fall off once a feed has cycled.
+const HISTORY_LIMIT: usize = 500;
+
+// An image pulled out of an RSS item, Atom entry or Reddit post, before filtering.
+struct FeedImage {
+    id: String,
+    url: String,
+    width: u32,
+    height: u32,
+    nsfw: bool,
+    author: Option<String>,
+    page_url: Option<String>,
+}
+
+fn looks_like_image(url: &str) -> bool {
+    let path = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    IMAGE_EXTENSIONS.iter().any(|ext| path.ends_with(ext))
+}
+
+fn child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children()
+        .find(|c| c.is_element() && c.tag_name().name() == name)
+}
+
+fn child_text(node: roxmltree::Node, name: &str) -> Option<String> {
+    child(node, name)
+        .and_then(|c| c.text())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+fn dimension(node: roxmltree::Node, attr: &str) -> u32 {
+    node.attribute(attr)
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0)
+}
+
+// Media RSS and enclosures are matched by local name, so the namespace prefix a
+// feed happens to use does not matter.
+fn xml_image(entry: roxmltree::Node) -> Option<(String, u32, u32)> {
+    for node in entry.descendants().filter(|n| n.is_element()) {
+        let url = match node.tag_name().name() {
+            "content" | "thumbnail" if node.attribute("url").is_some() => {
+                let medium_ok = node.attribute("medium").is_none_or(|m| m == "image");
+                let type_ok = node
+                    .attribute("type")
+                    .is_none_or(|t| t.starts_with("image/"));
+                node.attribute("url").filter(|_| medium_ok && type_ok)
+            }
+            "enclosure" => node.attribute("url").filter(|_| {
+                node.attribute("type")
+                    .is_some_and(|t| t.starts_with("image/"))
+            }),
+            "link" if node.attribute("rel") == Some("enclosure") => {
+                node.attribute("href").filter(|_| {
+                    node.attribute("type")
+                        .is_none_or(|t| t.starts_with("image/"))
+                })
+            }
+            _ => None,
+        };
+        if let Some(url) = url {
+            return Some((
+                url.to_string(),
+                dimension(node, "width"),
+                dimension(node, "height"),
+            ));
+        }
+    }
+    // Some photo blogs just link straight to the file.
+    child_text(entry, "link")
+        .or_else(|| child(entry, "link").and_then(|l| l.attribute("href").map(str::to_string)))
+        .filter(|l| looks_like_image(l))
+        .map(|l| (l, 0, 0))
+}
+
+fn xml_nsfw(entry: roxmltree::Node) -> bool {
+    entry.descendants().any(|n| {
+        n.is_element()
+            && n.tag_name().name() == "rating"
+            && n.text()
+                .is_some_and(|t| t.trim().eq_ignore_ascii_case("adult"))
+    })
+}
+
+fn parse_xml(body: &str) -> Result<Vec<FeedImage>, String> {
+    let doc = roxmltree::Document::parse(body).map_err(|e| format!("Invalid feed XML: {e}"))?;
+    let entries = doc
+        .descendants()
+        .filter(|n| n.is_element() && matches!(n.tag_name().name(), "item" | "entry"));
+
+    Ok(entries
+        .filter_map(|entry| {
+            let (url, width, height) = xml_image(entry)?;
+            let page_url = child(entry, "link").and_then(|l| {
+                l.attribute("href")
+                    .map(str::to_string)
+                    .or_else(|| l.text().map(|t| t.trim().to_string()))
+            });
+            let author = child_text(entry, "creator")
+                .or_else(|| child(entry, "author").and_then(|a| child_text(a, "name")))
+                .or_else(|| child_text(entry, "author"));
+            Some(FeedImage {
+                id: child_text(entry, "guid")
+                    .or_else(|| child_text(entry, "id"))
+                    .unwrap_or_else(|| url.clone()),
+                url,
+                width,
+                height,
+                nsfw: xml_nsfw(entry),
+                author,
+                page_url,
+            })
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct RedditListing {
+    data: RedditListingData,
+}
+
+#[derive(Deserialize)]
+struct RedditListingData {
+    children: Vec<RedditChild>,
+}
+
+#[derive(Deserialize)]
+struct RedditChild {
+    data: RedditPost,
+}
+
+#[derive(Deserialize)]
+struct RedditPost {
+    id: String,
+    url: String,
+    author: String,
+    permalink: String,
+    #[serde(default)]
+    over_18: bool,
+    #[serde(default)]
+    preview: Option<RedditPreview>,
+}
+
+#[derive(Deserialize)]
+struct RedditPreview {
+    images: Vec<RedditPreviewImage>,
+}
+
+#[derive(Deserialize)]
+struct RedditPreviewImage {
+    source: RedditImageSource,
+}
+
+#[derive(Deserialize)]
+struct RedditImageSource {
+    url: String,
+    width: u32,
+    height: u32,
+}
+
+fn parse_reddit(body: &str) -> Result<Vec<FeedImage>, String> {
+    let listing: RedditListing =
+        serde_json::from_str(body).map_err(|e| format!("Invalid Reddit listing: {e}"))?;
+    Ok(listing
+        .data
+        .children
+        .into_iter()
+        .filter_map(|child| {
+            let post = child.data;
+            let source = post
+                .preview
+                .and_then(|p| p.images.into_iter().next())
+                .map(|i| i.source);
+            // Direct links are the original upload; the preview is a fallback for
+            // hosts that wrap images in a page. Reddit HTML-escapes preview URLs.
+            let (url, width, height) = match (looks_like_image(&post.url), source) {
+                (true, Some(s)) => (post.url, s.width, s.height),
+                (true, None) => (post.url, 0, 0),
+                (false, Some(s)) => (s.url.replace("&amp;", "&"), s.width, s.height),
+                (false, None) => return None,
+            };
+            Some(FeedImage {
+                id: post.id,
+                url,
+                width,
+                height,
+                nsfw: post.over_18,
+                author: Some(format!("u/{}", post.author)),
+                page_url: Some(format!("https://www.reddit.com{}", post.permalink)),
+            })
+        })
+        .collect())
+}
+
+fn parse_feed(body: &str) -> Result<Vec<FeedImage>, String> {
+    if body.trim_start().starts_with('{') {
+        parse_reddit(body)
+    } else {
+        parse_xml(body)
+    }
+}
+
+// Images with unknown dimensions (0) pass; most RSS feeds do not report them.
+fn passes_filters(image: &FeedImage, options: &FeedOptions) -> bool {
+    if image.nsfw && !options.allow_nsfw {
+        return false;
+    }
+    if image.width == 0 || image.height == 0 {
+        return true;
+    }
+    if image.width < options.min_width || image.height < options.min_height {
+        return false;
+    }
+    let aspect = image.width as f64 / image.height as f64;
+    options.min_aspect.is_none_or(|min| aspect >= min)
+        && options.max_aspect.is_none_or(|max| aspect <= max)
+}
+
+// Remembers which feed items were already applied, keyed by feed URL, so a
+// feed that rarely changes does not keep serving the same post.
+#[derive(Default, serde::Serialize, Deserialize)]
+struct FeedHistory {
+    feeds: HashMap<String, VecDeque<String>>,
+}
+
+impl FeedHistory {
+    fn load(path: &PathBuf) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, path: &PathBuf) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+}
+
+pub struct FeedSource {
+    http: Client,
+    options: FeedOptions,
+    history_path: PathBuf,
+}
+
+impl FeedSource {
+    pub fn new(http: Client, options: FeedOptions, history_path: PathBuf) -> Self {
+        FeedSource {
+            http,
+            options,
+            history_path,
+        }
+    }
+
+    async fn fetch(&self) -> Result<Vec<Candidate>, String> {
+        let response = self
+            .http
+            .get(&self.options.url)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Feed request failed: HTTP {}", response.status()));
+        }
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        let provider = reqwest::Url::parse(&self.options.url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_else(|| "Feed".to_string());
+
+        Ok(parse_feed(&body)?
+            .into_iter()
+            .filter(|image| passes_filters(image, &self.options))
+            .map(|image| Candidate {
+                source: "feed".to_string(),
+                id: image.id,
+                width: image.width,
+                height: image.height,
+                preview_url: image.url.clone(),
+                download_url: image.url,
+                tracking_url: None,
+                attribution: Attribution {
+                    author: image.author.unwrap_or_else(|| provider.clone()),
+                    author_url: None,
+                    provider: provider.clone(),
+                    page_url: image.page_url,
+                    license: None,
+                },
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl WallpaperSource for FeedSource {
+    fn key(&self) -> &'static str {
+        "feed"
+    }
+
+    // Feeds are not paginated; everything arrives on the first page.
+    async fn list(&self, page: u32) -> Result<Vec<Candidate>, String> {
+        if page > 1 {
+            return Ok(Vec::new());
+        }
+        self.fetch().await
+    }
+
+    async fn search(&self, query: &str, page: u32) -> Result<Vec<Candidate>, String> {
+        let query = query.to_lowercase();
+        Ok(self
+            .list(page)
+            .await?
+            .into_iter()
+            .filter(|c| c.attribution.author.to_lowercase().contains(&query))
+            .collect())
+    }
+
+    // Prefers items this feed has not shown yet; once everything has been seen it
+    // falls back to the whole feed.
+    async fn random(&self) -> Result<Candidate, String> {
+        let candidates = self.fetch().await?;
+        let history = FeedHistory::load(&self.history_path);
+        let seen = history.feeds.get(&self.options.url);
+        let unseen: Vec<&Candidate> = candidates
+            .iter()
+            .filter(|c| seen.is_none_or(|s| !s.contains(&c.id)))
+            .collect();
+        let pool = if unseen.is_empty() {
+            candidates.iter().collect()
+        } else {
+            unseen
+        };
+        pool.choose(&mut rand::thread_rng())
+            .map(|c| (*c).clone())
+            .ok_or_else(|| "Feed has no images matching the filters".to_string())
+    }
+
+    fn on_applied(&self, _app: &tauri::AppHandle, candidate: &Candidate) {
+        let mut history = FeedHistory::load(&self.history_path);
+        let seen = history.feeds.entry(self.options.url.clone()).or_default();
+        seen.retain(|id| id != &candidate.id);
+        seen.push_back(candidate.id.clone());
+        while seen.len() > HISTORY_LIMIT {
+            seen.pop_front();
+        }
+        if let Err(e) = history.save(&self.history_path) {
+            eprintln!("[WallCraft] failed to save feed history: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/feed/",
+                $name
+            ))
+        };
+    }
+
+    fn feed_options() -> FeedOptions {
+        FeedOptions {
+            url: "https://photos.example.com/feed".to_string(),
+            min_width: 0,
+            min_height: 0,
+            min_aspect: None,
+            max_aspect: None,
+            allow_nsfw: false,
+        }
+    }
+
+    fn image(width: u32, height: u32) -> FeedImage {
+        FeedImage {
+            id: "a".to_string(),
+            url: "https://cdn.example.com/a.jpg".to_string(),
+            width,
+            height,
+            nsfw: false,
+            author: None,
+            page_url: None,
+        }
+    }
+
+    #[test]
+    fn parses_rss_media_and_enclosures() {
+        let images = parse_feed(fixture!("rss.xml")).unwrap();
+        let ids: Vec<&str> = images.iter().map(|i| i.id.as_str()).collect();
+        // The audio enclosure is not an image.
+        assert_eq!(
+            ids,
+            [
+                "fjord-1",
+                "desert-2",
+                "https://cdn.example.com/glacier.webp?size=full",
+                "night-5"
+            ]
+        );
+
+        let fjord = &images[0];
+        assert_eq!(fjord.url, "https://cdn.example.com/fjord.jpg");
+        assert_eq!((fjord.width, fjord.height), (5472, 3648));
+        assert_eq!(fjord.author.as_deref(), Some("Ingrid"));
+        assert_eq!(
+            fjord.page_url.as_deref(),
+            Some("https://photos.example.com/posts/fjord")
+        );
+
+        let desert = &images[1];
+        assert_eq!(desert.url, "https://cdn.example.com/desert.png");
+        assert_eq!((desert.width, desert.height), (0, 0));
+        // A link straight to an image file is the image.
+        assert_eq!(
+            images[2].url,
+            "https://cdn.example.com/glacier.webp?size=full"
+        );
+        assert!(!fjord.nsfw && images[3].nsfw);
+    }
+
+    #[test]
+    fn parses_atom_enclosure_links() {
+        let images = parse_feed(fixture!("atom.xml")).unwrap();
+        assert_eq!(images.len(), 1);
+        let ridge = &images[0];
+        assert_eq!(ridge.id, "tag:walls.example.org,2026:ridge");
+        assert_eq!(ridge.url, "https://walls.example.org/files/ridge.jpg");
+        assert_eq!(ridge.author.as_deref(), Some("Tomas"));
+        assert_eq!(
+            ridge.page_url.as_deref(),
+            Some("https://walls.example.org/ridge")
+        );
+    }
+
+    #[test]
+    fn parses_reddit_listings() {
+        let images = parse_feed(fixture!("reddit.json")).unwrap();
+        // The text post has nothing to show.
+        assert_eq!(images.len(), 3);
+
+        let aurora = &images[0];
+        assert_eq!(aurora.url, "https://i.redd.it/aurora.jpg");
+        assert_eq!((aurora.width, aurora.height), (4032, 2268));
+        assert_eq!(aurora.author.as_deref(), Some("u/northern_sky"));
+        assert_eq!(
+            aurora.page_url.as_deref(),
+            Some("https://www.reddit.com/r/EarthPorn/comments/1c1/aurora/")
+        );
+
+        // Gallery pages fall back to the preview, unescaped.
+        assert_eq!(
+            images[1].url,
+            "https://preview.redd.it/coast.jpg?width=1080&format=pjpg&auto=webp"
+        );
+        assert!(!aurora.nsfw && images[2].nsfw);
+    }
+
+    #[test]
+    fn nsfw_posts_need_opting_in() {
+        let images = parse_feed(fixture!("reddit.json")).unwrap();
+        let mut options = feed_options();
+        let shown: Vec<&str> = images
+            .iter()
+            .filter(|i| passes_filters(i, &options))
+            .map(|i| i.id.as_str())
+            .collect();
+        assert_eq!(shown, ["1c1", "1c2"]);
+
+        options.allow_nsfw = true;
+        assert!(images.iter().all(|i| passes_filters(i, &options)));
+    }
+
+    #[test]
+    fn filters_by_size_and_aspect() {
+        let mut options = feed_options();
+        options.min_width = 1920;
+        options.min_height = 1080;
+        assert!(passes_filters(&image(1920, 1080), &options));
+        assert!(!passes_filters(&image(1280, 1080), &options));
+        assert!(!passes_filters(&image(2560, 1000), &options));
+        // Most feeds do not say how big their images are.
+        assert!(passes_filters(&image(0, 0), &options));
+
+        let mut options = feed_options();
+        options.min_aspect = Some(1.5);
+        options.max_aspect = Some(2.5);
+        assert!(passes_filters(&image(1920, 1080), &options));
+        assert!(passes_filters(&image(3440, 1440), &options));
+        assert!(!passes_filters(&image(1080, 1920), &options));
+        assert!(!passes_filters(&image(1200, 1000), &options));
+        assert!(!passes_filters(&image(5120, 1440), &options));
+
+        // Portrait posts from the listing go when only landscapes are wanted.
+        let images = parse_feed(fixture!("reddit.json")).unwrap();
+        let shown: Vec<&str> = images
+            .iter()
+            .filter(|i| passes_filters(i, &options))
+            .map(|i| i.id.as_str())
+            .collect();
+        assert_eq!(shown, ["1c1"]);
+    }
+}
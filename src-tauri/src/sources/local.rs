@@ -0,0 +1,348 @@
+use async_trait::async_trait;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use rand::seq::SliceRandom;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::SystemTime,
+};
+
+use super::{Attribution, Candidate, DownloadTarget, WallpaperSource};
+use crate::settings::{FolderOrder, LocalFolderOptions};
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+const PAGE_SIZE: usize = 50;
+// Folder setups kept scanned and watched at once; playlists rarely use more.
+const MAX_FOLDERS: usize = 8;
+
+#[derive(Clone)]
+struct LocalFile {
+    path: PathBuf,
+    modified: SystemTime,
+}
+
+#[derive(Default)]
+struct LibraryState {
+    files: Vec<LocalFile>,
+    // Shuffle draws from a bag so every file is shown once before any repeats.
+    shuffle_bag: Vec<PathBuf>,
+    last_shown: Option<LocalFile>,
+}
+
+// Scanned contents of the configured wallpaper folders. It lives in AppState so the
+// file watchers and the rotation positions outlive the per-rotation source objects.
+#[derive(Default)]
+pub struct FolderLibrary {
+    // One entry per folder setup, most recently used first, so a playlist that
+    // alternates between folders keeps each one's place.
+    folders: Mutex<Vec<Arc<Folder>>>,
+}
+
+/// The files and rotation position of one folder setup.
+pub struct Folder {
+    options: LocalFolderOptions,
+    state: Mutex<LibraryState>,
+    // Set by the watcher; the next rotation rescans before picking.
+    dirty: Arc<AtomicBool>,
+    _watcher: RecommendedWatcher,
+}
+
+impl FolderLibrary {
+    // Scans and watches folders the first time they are used.
+    pub fn configure(&self, options: &LocalFolderOptions) -> Result<Arc<Folder>, String> {
+        let mut folders = self.folders.lock().unwrap();
+        if let Some(i) = folders.iter().position(|f| &f.options == options) {
+            let folder = folders.remove(i);
+            folders.insert(0, folder.clone());
+            return Ok(folder);
+        }
+        if options.directories.is_empty() {
+            return Err("No wallpaper folders configured".to_string());
+        }
+
+        let dirty = Arc::new(AtomicBool::new(true));
+        let flag = dirty.clone();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if !event.kind.is_access() {
+                    flag.store(true, Ordering::SeqCst);
+                }
+            }
+        })
+        .map_err(|e| e.to_string())?;
+        for dir in &options.directories {
+            watcher
+                .watch(Path::new(dir), RecursiveMode::Recursive)
+                .map_err(|e| format!("Cannot watch {dir}: {e}"))?;
+        }
+
+        let folder = Arc::new(Folder {
+            options: options.clone(),
+            state: Mutex::new(LibraryState::default()),
+            dirty,
+            _watcher: watcher,
+        });
+        folders.insert(0, folder.clone());
+        folders.truncate(MAX_FOLDERS);
+        Ok(folder)
+    }
+}
+
+impl Folder {
+    fn refresh(&self, state: &mut LibraryState) {
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        let options = &self.options;
+        let mut files = Vec::new();
+        for dir in &options.directories {
+            scan_dir(Path::new(dir), &mut files);
+        }
+        // Overlapping folders would otherwise list the same file twice.
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files.dedup_by(|a, b| a.path == b.path);
+        if options.order == FolderOrder::NewestFirst {
+            files.sort_by_key(|f| std::cmp::Reverse(f.modified));
+        }
+        state
+            .shuffle_bag
+            .retain(|p| files.iter().any(|f| &f.path == p));
+        state.files = files;
+    }
+
+    fn listing(&self) -> Vec<LocalFile> {
+        let mut state = self.state.lock().unwrap();
+        self.refresh(&mut state);
+        state.files.clone()
+    }
+
+    fn next(&self) -> Option<LocalFile> {
+        let mut state = self.state.lock().unwrap();
+        self.refresh(&mut state);
+        if state.files.is_empty() {
+            return None;
+        }
+        let next = match self.options.order {
+            FolderOrder::Shuffle => {
+                if state.shuffle_bag.is_empty() {
+                    let mut bag: Vec<PathBuf> =
+                        state.files.iter().map(|f| f.path.clone()).collect();
+                    bag.shuffle(&mut rand::thread_rng());
+                    state.shuffle_bag = bag;
+                }
+                let path = state.shuffle_bag.pop()?;
+                state.files.iter().find(|f| f.path == path).cloned()
+            }
+            FolderOrder::Sequential => {
+                let position = state
+                    .last_shown
+                    .as_ref()
+                    .and_then(|last| state.files.iter().position(|f| f.path == last.path))
+                    .map_or(0, |i| i + 1);
+                state.files.get(position % state.files.len()).cloned()
+            }
+            FolderOrder::NewestFirst => {
+                // Anything added since the last pick jumps the queue.
+                let position = match &state.last_shown {
+                    Some(last) if state.files[0].modified <= last.modified => state
+                        .files
+                        .iter()
+                        .position(|f| f.path == last.path)
+                        .map_or(0, |i| i + 1),
+                    _ => 0,
+                };
+                state.files.get(position % state.files.len()).cloned()
+            }
+        };
+        state.last_shown = next.clone();
+        next
+    }
+
+    // Rotation may skip files it drew but did not show; the order continues
+    // from the one actually on screen.
+    fn mark_shown(&self, path: &Path) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(file) = state.files.iter().find(|f| f.path == path).cloned() {
+            state.last_shown = Some(file);
+        }
+    }
+}
+
+fn scan_dir(dir: &Path, files: &mut Vec<LocalFile>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            scan_dir(&path, files);
+        } else if is_image(&path) {
+            files.push(LocalFile {
+                path,
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+    }
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+fn candidate(file: &LocalFile) -> Candidate {
+    let path = file.path.to_string_lossy().to_string();
+    // Reading the header is cheap; undecodable files are caught by the pipeline.
+    let (width, height) = image::image_dimensions(&file.path).unwrap_or((0, 0));
+    let name = file
+        .path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.clone());
+    Candidate {
+        source: "local".to_string(),
+        id: path.clone(),
+        width,
+        height,
+        preview_url: path.clone(),
+        download_url: path,
+        tracking_url: None,
+        attribution: Attribution {
+            author: name,
+            author_url: None,
+            provider: "Local folder".to_string(),
+            page_url: None,
+            license: None,
+        },
+    }
+}
+
+pub struct LocalFolderSource {
+    library: Arc<Folder>,
+}
+
+impl LocalFolderSource {
+    pub fn new(library: Arc<Folder>) -> Self {
+        LocalFolderSource { library }
+    }
+}
+
+#[async_trait]
+impl WallpaperSource for LocalFolderSource {
+    fn key(&self) -> &'static str {
+        "local"
+    }
+
+    async fn list(&self, page: u32) -> Result<Vec<Candidate>, String> {
+        let start = (page.max(1) as usize - 1) * PAGE_SIZE;
+        Ok(self
+            .library
+            .listing()
+            .iter()
+            .skip(start)
+            .take(PAGE_SIZE)
+            .map(candidate)
+            .collect())
+    }
+
+    async fn search(&self, query: &str, page: u32) -> Result<Vec<Candidate>, String> {
+        let query = query.to_lowercase();
+        let start = (page.max(1) as usize - 1) * PAGE_SIZE;
+        Ok(self
+            .library
+            .listing()
+            .iter()
+            .filter(|f| f.path.to_string_lossy().to_lowercase().contains(&query))
+            .skip(start)
+            .take(PAGE_SIZE)
+            .map(candidate)
+            .collect())
+    }
+
+    // Follows the configured order instead of sampling pages.
+    async fn random(&self) -> Result<Candidate, String> {
+        self.library
+            .next()
+            .map(|file| candidate(&file))
+            .ok_or_else(|| "No images found in the configured folders".to_string())
+    }
+
+    async fn resolve_download(&self, candidate: &Candidate) -> Result<DownloadTarget, String> {
+        Ok(DownloadTarget::File(PathBuf::from(&candidate.download_url)))
+    }
+
+    fn on_applied(&self, _app: &tauri::AppHandle, candidate: &Candidate) {
+        self.library.mark_shown(Path::new(&candidate.download_url));
+    }
+
+    fn pool_size(&self) -> Option<usize> {
+        Some(self.library.listing().len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder_with(name: &str, files: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wallcraft-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for file in files {
+            fs::write(dir.join(file), b"").unwrap();
+        }
+        dir
+    }
+
+    fn sequential(dir: &Path) -> LocalFolderOptions {
+        LocalFolderOptions {
+            directories: vec![dir.to_string_lossy().to_string()],
+            order: FolderOrder::Sequential,
+        }
+    }
+
+    fn next_name(folder: &Folder) -> String {
+        let file = folder.next().unwrap();
+        file.path.file_name().unwrap().to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn alternating_folders_keep_their_place() {
+        let photos = folder_with("library-photos", &["a.jpg", "b.jpg", "c.jpg"]);
+        let art = folder_with("library-art", &["x.png", "y.png"]);
+        let library = FolderLibrary::default();
+
+        // A playlist switching between the two folders on every rotation.
+        let mut shown = Vec::new();
+        for _ in 0..3 {
+            shown.push(next_name(&library.configure(&sequential(&photos)).unwrap()));
+            shown.push(next_name(&library.configure(&sequential(&art)).unwrap()));
+        }
+        assert_eq!(
+            shown,
+            ["a.jpg", "x.png", "b.jpg", "y.png", "c.jpg", "x.png"]
+        );
+
+        let again = library.configure(&sequential(&photos)).unwrap();
+        assert!(Arc::ptr_eq(
+            &again,
+            &library.configure(&sequential(&photos)).unwrap()
+        ));
+        assert!(library
+            .configure(&LocalFolderOptions {
+                directories: Vec::new(),
+                order: FolderOrder::Sequential,
+            })
+            .is_err());
+        let _ = fs::remove_dir_all(&photos);
+        let _ = fs::remove_dir_all(&art);
+    }
+}
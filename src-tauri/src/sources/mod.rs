@@ -0,0 +1,162 @@
+mod daily;
+mod favorites;
+mod feed;
+mod json_feed;
+pub mod local;
+mod pexels;
+mod solid;
+mod unsplash;
+mod wallhaven;
+
+use async_trait::async_trait;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::settings::SourceConfig;
+
+pub use solid::parse_color;
+pub use unsplash::{candidate as unsplash_candidate, UnsplashSource};
+
+// Photos are drawn from the first few pages so rotation does not keep landing on
+// the same handful of featured images.
+const PAGES_TO_SAMPLE: u32 = 5;
+
+/// Credit shown next to a wallpaper, as required by most providers' terms.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Attribution {
+    pub author: String,
+    #[serde(default)]
+    pub author_url: Option<String>,
+    pub provider: String,
+    #[serde(default)]
+    pub page_url: Option<String>,
+    #[serde(default)]
+    pub license: Option<String>,
+}
+
+/// A photo offered by a source, before anything has been downloaded.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Candidate {
+    /// Key of the source that produced it, e.g. `unsplash` or `pexels`.
+    pub source: String,
+    pub id: String,
+    pub width: u32,
+    pub height: u32,
+    pub preview_url: String,
+    /// Full-resolution image; sources may still rewrite it in `resolve_download`.
+    pub download_url: String,
+    /// Endpoint the provider wants pinged whenever the photo is used.
+    #[serde(default)]
+    pub tracking_url: Option<String>,
+    pub attribution: Attribution,
+}
+
+/// Where the apply pipeline reads the full-resolution image from.
+pub enum DownloadTarget {
+    Url(String),
+    File(PathBuf),
+    /// An image the source made itself.
+    Bytes(Vec<u8>),
+}
+
+#[async_trait]
+pub trait WallpaperSource: Send + Sync {
+    fn key(&self) -> &'static str;
+
+    /// One page of the source's default listing (curated feed, topic, folder...).
+    async fn list(&self, page: u32) -> Result<Vec<Candidate>, String>;
+
+    async fn search(&self, query: &str, page: u32) -> Result<Vec<Candidate>, String>;
+
+    /// Picks one candidate for rotation. The default samples a random page of `list`.
+    async fn random(&self) -> Result<Candidate, String> {
+        let page = rand::thread_rng().gen_range(1..=PAGES_TO_SAMPLE);
+        let mut candidates = self.list(page).await?;
+        // Small feeds may not have that many pages.
+        if candidates.is_empty() && page > 1 {
+            candidates = self.list(1).await?;
+        }
+        candidates
+            .choose(&mut rand::thread_rng())
+            .cloned()
+            .ok_or_else(|| format!("No photos found in {}", self.key()))
+    }
+
+    /// Where the apply pipeline should fetch this candidate from.
+    async fn resolve_download(&self, candidate: &Candidate) -> Result<DownloadTarget, String> {
+        Ok(DownloadTarget::Url(candidate.download_url.clone()))
+    }
+
+    /// Called once the candidate is on screen.
+    fn on_applied(&self, _app: &AppHandle, _candidate: &Candidate) {}
+
+    /// How many wallpapers the source has in all, for sources that know.
+    fn pool_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+// `scheduled` marks timer-driven rotation, which must leave an API quota reserve.
+pub fn build(
+    app: &AppHandle,
+    config: &SourceConfig,
+    scheduled: bool,
+) -> Result<Box<dyn WallpaperSource>, String> {
+    crate::secrets::ensure_source_resolved(config)?;
+    let settings = crate::settings::load_settings_inner(app);
+    Ok(match config {
+        SourceConfig::Unsplash { rotation } => {
+            let mut client = crate::unsplash::UnsplashClient::for_app(app)?;
+            if scheduled {
+                client = client.scheduled();
+            }
+            Box::new(UnsplashSource::new(client, rotation.clone()))
+        }
+        SourceConfig::Pexels { api_key, query } => Box::new(pexels::PexelsSource::new(
+            crate::http::client(&settings)?,
+            api_key.clone(),
+            query.clone(),
+        )),
+        SourceConfig::Wallhaven(options) => Box::new(wallhaven::WallhavenSource::new(
+            crate::http::client(&settings)?,
+            options.clone(),
+        )),
+        SourceConfig::JsonFeed(feed) => Box::new(json_feed::JsonFeedSource::new(
+            crate::http::client(&settings)?,
+            feed.clone(),
+        )),
+        SourceConfig::Feed(options) => Box::new(feed::FeedSource::new(
+            crate::http::client(&settings)?,
+            options.clone(),
+            app.path()
+                .app_data_dir()
+                .map_err(|e| e.to_string())?
+                .join("feed_history.json"),
+        )),
+        SourceConfig::Daily(feed) => Box::new(daily::DailySource::new(
+            crate::http::client(&settings)?,
+            feed.clone(),
+        )),
+        SourceConfig::LocalFolder(options) => {
+            let folder = app
+                .state::<crate::AppState>()
+                .folder_library
+                .configure(options)?;
+            Box::new(local::LocalFolderSource::new(folder))
+        }
+        SourceConfig::Favorites => Box::new(favorites::FavoritesSource::new(app.clone())),
+        SourceConfig::SolidColor { color } => Box::new(solid::SolidColorSource::new(color)?),
+        // The scheduler resolves playlists and day/night sets to one of their
+        // entries first, and shows wallpaper sets itself.
+        SourceConfig::Playlist(_) | SourceConfig::Solar(_) | SourceConfig::DynamicSet(_) => {
+            return Err(
+                "Playlists, day/night and wallpaper sets are not sources by themselves"
+                    .to_string(),
+            );
+        }
+    })
+}
@@ -0,0 +1,115 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{Attribution, Candidate, WallpaperSource};
+
+const BASE_URL: &str = "https://api.pexels.com/v1";
+const PER_PAGE: u32 = 30;
+
+#[derive(Deserialize)]
+struct PhotoPage {
+    photos: Vec<PexelsPhoto>,
+}
+
+#[derive(Deserialize)]
+struct PexelsPhoto {
+    id: u64,
+    width: u32,
+    height: u32,
+    url: String,
+    photographer: String,
+    photographer_url: String,
+    src: PexelsSrc,
+}
+
+#[derive(Deserialize)]
+struct PexelsSrc {
+    original: String,
+    large: String,
+}
+
+pub struct PexelsSource {
+    http: Client,
+    api_key: String,
+    query: Option<String>,
+}
+
+impl PexelsSource {
+    pub fn new(http: Client, api_key: String, query: Option<String>) -> Self {
+        PexelsSource {
+            http,
+            api_key,
+            query: query.filter(|q| !q.trim().is_empty()),
+        }
+    }
+
+    async fn get(&self, path: &str, query: &[(&str, String)]) -> Result<Vec<Candidate>, String> {
+        if self.api_key.is_empty() {
+            return Err("Pexels API key is not configured".to_string());
+        }
+        let response = self
+            .http
+            .get(format!("{BASE_URL}{path}"))
+            .header("Authorization", &self.api_key)
+            .query(query)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Pexels request {path} failed: HTTP {}",
+                response.status()
+            ));
+        }
+        let page: PhotoPage = response.json().await.map_err(|e| e.to_string())?;
+        Ok(page.photos.into_iter().map(candidate).collect())
+    }
+}
+
+fn candidate(photo: PexelsPhoto) -> Candidate {
+    Candidate {
+        source: "pexels".to_string(),
+        id: photo.id.to_string(),
+        width: photo.width,
+        height: photo.height,
+        preview_url: photo.src.large,
+        download_url: photo.src.original,
+        tracking_url: None,
+        attribution: Attribution {
+            author: photo.photographer,
+            author_url: Some(photo.photographer_url),
+            provider: "Pexels".to_string(),
+            page_url: Some(photo.url),
+            license: Some("Pexels License".to_string()),
+        },
+    }
+}
+
+fn page_query(page: u32) -> Vec<(&'static str, String)> {
+    vec![
+        ("page", page.max(1).to_string()),
+        ("per_page", PER_PAGE.to_string()),
+    ]
+}
+
+#[async_trait]
+impl WallpaperSource for PexelsSource {
+    fn key(&self) -> &'static str {
+        "pexels"
+    }
+
+    async fn list(&self, page: u32) -> Result<Vec<Candidate>, String> {
+        match &self.query {
+            Some(query) => self.search(query, page).await,
+            None => self.get("/curated", &page_query(page)).await,
+        }
+    }
+
+    async fn search(&self, query: &str, page: u32) -> Result<Vec<Candidate>, String> {
+        let mut params = page_query(page);
+        params.push(("query", query.to_string()));
+        params.push(("orientation", "landscape".to_string()));
+        self.get("/search", &params).await
+    }
+}
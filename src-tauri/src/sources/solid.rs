@@ -0,0 +1,128 @@
+use async_trait::async_trait;
+use image::{ImageFormat, Rgb, RgbImage};
+use std::io::Cursor;
+
+use super::{Attribution, Candidate, DownloadTarget, WallpaperSource};
+
+// The crop step scales this up to each screen, so a few pixels are enough.
+const IMAGE_SIZE: u32 = 8;
+
+/// Reads a `#rrggbb` colour.
+pub fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.trim().strip_prefix('#')?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// Always offers the same single colour, so rotation leaves it alone once shown.
+pub struct SolidColorSource {
+    rgb: [u8; 3],
+}
+
+impl SolidColorSource {
+    pub fn new(color: &str) -> Result<Self, String> {
+        parse_color(color)
+            .map(|rgb| SolidColorSource { rgb })
+            .ok_or_else(|| format!("Invalid colour {color}"))
+    }
+
+    fn hex(&self) -> String {
+        let [r, g, b] = self.rgb;
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+
+    fn candidate(&self) -> Candidate {
+        let hex = self.hex();
+        // An inline SVG swatch, so previews need no file.
+        let swatch = format!(
+            "data:image/svg+xml,<svg xmlns='http://www.w3.org/2000/svg' width='16' height='9'>\
+             <rect width='16' height='9' fill='%23{}'/></svg>",
+            &hex[1..]
+        );
+        Candidate {
+            source: "solidColor".to_string(),
+            id: hex.clone(),
+            width: IMAGE_SIZE,
+            height: IMAGE_SIZE,
+            preview_url: swatch.clone(),
+            download_url: swatch,
+            tracking_url: None,
+            attribution: Attribution {
+                author: hex,
+                author_url: None,
+                provider: "Solid colour".to_string(),
+                page_url: None,
+                license: None,
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl WallpaperSource for SolidColorSource {
+    fn key(&self) -> &'static str {
+        "solidColor"
+    }
+
+    async fn list(&self, page: u32) -> Result<Vec<Candidate>, String> {
+        Ok(if page <= 1 {
+            vec![self.candidate()]
+        } else {
+            Vec::new()
+        })
+    }
+
+    async fn search(&self, _query: &str, page: u32) -> Result<Vec<Candidate>, String> {
+        self.list(page).await
+    }
+
+    async fn random(&self) -> Result<Candidate, String> {
+        Ok(self.candidate())
+    }
+
+    fn pool_size(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    async fn resolve_download(&self, _candidate: &Candidate) -> Result<DownloadTarget, String> {
+        let image = RgbImage::from_pixel(IMAGE_SIZE, IMAGE_SIZE, Rgb(self.rgb));
+        let mut png = Cursor::new(Vec::new());
+        image
+            .write_to(&mut png, ImageFormat::Png)
+            .map_err(|e| e.to_string())?;
+        Ok(DownloadTarget::Bytes(png.into_inner()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_colours() {
+        assert_eq!(parse_color("#1E1e1e"), Some([0x1e, 0x1e, 0x1e]));
+        assert_eq!(parse_color(" #ff8000 "), Some([255, 128, 0]));
+        for bad in ["1e1e1e", "#1e1e1", "#1e1e1e1", "#gg0000", "#+1+1+1", ""] {
+            assert_eq!(parse_color(bad), None, "{bad}");
+        }
+    }
+
+    #[tokio::test]
+    async fn offers_one_image_of_the_colour() {
+        let source = SolidColorSource::new("#1E1E1E").unwrap();
+        let candidate = source.random().await.unwrap();
+        assert_eq!(candidate.id, "#1e1e1e");
+        assert!(candidate.preview_url.contains("fill='%231e1e1e'"));
+        assert_eq!(source.list(2).await.unwrap().len(), 0);
+
+        let DownloadTarget::Bytes(png) = source.resolve_download(&candidate).await.unwrap() else {
+            panic!("expected generated image data");
+        };
+        let image = image::load_from_memory(&png).unwrap().to_rgb8();
+        assert_eq!(image.get_pixel(3, 3), &Rgb([0x1e, 0x1e, 0x1e]));
+        assert!(SolidColorSource::new("dark").is_err());
+    }
+}
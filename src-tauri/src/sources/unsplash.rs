@@ -0,0 +1,109 @@
+use async_trait::async_trait;
+use rand::{seq::SliceRandom, Rng};
+use tauri::AppHandle;
+
+use super::{Attribution, Candidate, WallpaperSource, PAGES_TO_SAMPLE};
+use crate::settings::RotationSource;
+use crate::unsplash::{self, Photo, UnsplashClient};
+
+pub struct UnsplashSource {
+    client: UnsplashClient,
+    rotation: RotationSource,
+}
+
+impl UnsplashSource {
+    pub fn new(client: UnsplashClient, rotation: RotationSource) -> Self {
+        UnsplashSource { client, rotation }
+    }
+
+    async fn fetch(&self, page: u32) -> Result<Vec<Photo>, String> {
+        let client = &self.client;
+        match &self.rotation {
+            RotationSource::Editorial => client.photos(page).await,
+            RotationSource::Topic { slug } => client.topic_photos(slug, page).await,
+            RotationSource::Collection { id } => client.collection_photos(id, page).await,
+            RotationSource::User { username } => client.user_photos(username, page).await,
+            RotationSource::Search { query } => {
+                Ok(client.search_photos(query, page).await?.results)
+            }
+            RotationSource::Random {
+                orientation,
+                content_filter,
+                query,
+            } => Ok(vec![
+                client
+                    .random_photo(
+                        orientation.as_deref(),
+                        content_filter.as_deref(),
+                        query.as_deref(),
+                    )
+                    .await?,
+            ]),
+        }
+    }
+}
+
+pub fn candidate(photo: &Photo) -> Candidate {
+    Candidate {
+        source: "unsplash".to_string(),
+        id: photo.id.clone(),
+        width: photo.width,
+        height: photo.height,
+        preview_url: photo.urls.regular.clone(),
+        download_url: unsplash::full_res_url(photo),
+        tracking_url: Some(photo.links.download_location.clone()),
+        attribution: Attribution {
+            author: photo.user.name.clone(),
+            author_url: Some(photo.user.links.html.clone()),
+            provider: "Unsplash".to_string(),
+            page_url: photo.links.html.clone(),
+            license: Some("Unsplash License".to_string()),
+        },
+    }
+}
+
+#[async_trait]
+impl WallpaperSource for UnsplashSource {
+    fn key(&self) -> &'static str {
+        "unsplash"
+    }
+
+    async fn list(&self, page: u32) -> Result<Vec<Candidate>, String> {
+        Ok(self.fetch(page).await?.iter().map(candidate).collect())
+    }
+
+    async fn search(&self, query: &str, page: u32) -> Result<Vec<Candidate>, String> {
+        let results = self.client.search_photos(query, page).await?.results;
+        Ok(results.iter().map(candidate).collect())
+    }
+
+    async fn random(&self) -> Result<Candidate, String> {
+        if matches!(self.rotation, RotationSource::Random { .. }) {
+            return self
+                .list(1)
+                .await?
+                .pop()
+                .ok_or_else(|| "Unsplash returned no random photo".to_string());
+        }
+        // Sampling deeper pages costs extra requests, so stay on page 1 when quota is low.
+        let page = if self.client.quota_is_low() {
+            1
+        } else {
+            rand::thread_rng().gen_range(1..=PAGES_TO_SAMPLE)
+        };
+        let mut candidates = self.list(page).await?;
+        if candidates.is_empty() && page > 1 {
+            candidates = self.list(1).await?;
+        }
+        candidates
+            .choose(&mut rand::thread_rng())
+            .cloned()
+            .ok_or_else(|| "No photos found for the configured source".to_string())
+    }
+
+    fn on_applied(&self, app: &AppHandle, candidate: &Candidate) {
+        if let Some(location) = &candidate.tracking_url {
+            unsplash::track_download_in_background(app, location.clone());
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{Attribution, Candidate, WallpaperSource};
+use crate::settings::WallhavenOptions;
+
+const BASE_URL: &str = "https://wallhaven.cc/api/v1";
+
+#[derive(Deserialize)]
+struct SearchPage {
+    data: Vec<Wallpaper>,
+}
+
+#[derive(Deserialize)]
+struct Wallpaper {
+    id: String,
+    url: String,
+    dimension_x: u32,
+    dimension_y: u32,
+    path: String,
+    thumbs: Thumbs,
+    #[serde(default)]
+    source: String,
+}
+
+#[derive(Deserialize)]
+struct Thumbs {
+    large: String,
+}
+
+pub struct WallhavenSource {
+    http: Client,
+    options: WallhavenOptions,
+}
+
+impl WallhavenSource {
+    pub fn new(http: Client, options: WallhavenOptions) -> Self {
+        WallhavenSource { http, options }
+    }
+
+    async fn search_sorted(
+        &self,
+        query: &str,
+        sorting: &str,
+        page: u32,
+    ) -> Result<Vec<Candidate>, String> {
+        let options = &self.options;
+        let mut params = vec![
+            ("q", query.to_string()),
+            ("sorting", sorting.to_string()),
+            ("page", page.max(1).to_string()),
+            ("categories", options.categories.clone()),
+            ("purity", options.purity.clone()),
+        ];
+        if !options.at_least.is_empty() {
+            params.push(("atleast", options.at_least.clone()));
+        }
+        // NSFW purity levels only work with a key; Wallhaven ignores them otherwise.
+        if let Some(key) = options.api_key.as_ref().filter(|k| !k.is_empty()) {
+            params.push(("apikey", key.clone()));
+        }
+
+        let response = self
+            .http
+            .get(format!("{BASE_URL}/search"))
+            .query(&params)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Wallhaven search failed: HTTP {}",
+                response.status()
+            ));
+        }
+        let page: SearchPage = response.json().await.map_err(|e| e.to_string())?;
+        Ok(page.data.into_iter().map(candidate).collect())
+    }
+}
+
+fn candidate(wallpaper: Wallpaper) -> Candidate {
+    // Wallhaven does not expose the uploader in search results; credit the
+    // original source when the uploader recorded one.
+    let author_url = Some(wallpaper.source).filter(|s| !s.is_empty());
+    Candidate {
+        source: "wallhaven".to_string(),
+        id: wallpaper.id,
+        width: wallpaper.dimension_x,
+        height: wallpaper.dimension_y,
+        preview_url: wallpaper.thumbs.large,
+        download_url: wallpaper.path,
+        tracking_url: None,
+        attribution: Attribution {
+            author: "Wallhaven".to_string(),
+            author_url,
+            provider: "Wallhaven".to_string(),
+            page_url: Some(wallpaper.url),
+            license: None,
+        },
+    }
+}
+
+#[async_trait]
+impl WallpaperSource for WallhavenSource {
+    fn key(&self) -> &'static str {
+        "wallhaven"
+    }
+
+    async fn list(&self, page: u32) -> Result<Vec<Candidate>, String> {
+        self.search_sorted(&self.options.query, "toplist", page)
+            .await
+    }
+
+    async fn search(&self, query: &str, page: u32) -> Result<Vec<Candidate>, String> {
+        self.search_sorted(query, "relevance", page).await
+    }
+
+    async fn random(&self) -> Result<Candidate, String> {
+        self.search_sorted(&self.options.query, "random", 1)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| "Wallhaven returned no wallpapers".to_string())
+    }
+}
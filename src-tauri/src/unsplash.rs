@@ -0,0 +1,837 @@
+use reqwest::{header::HeaderMap, Client, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tauri::{AppHandle, Manager};
+
+use crate::settings::Settings;
+
+const BASE_URL: &str = "https://api.unsplash.com";
+const PER_PAGE: u32 = 20;
+
+// Unsplash quotas reset hourly, so older readings say nothing about the budget.
+const QUOTA_STALE_SECS: u64 = 60 * 60;
+// Below this share of the hourly limit, pagination and background prefetching stop.
+const LOW_QUOTA_DIVISOR: u32 = 5;
+// Scheduled rotation never spends the last tenth of the quota (at least this many
+// requests), leaving it for the user browsing the gallery.
+const MIN_SCHEDULED_RESERVE: u32 = 5;
+
+const TRACK_DOWNLOAD_ATTEMPTS: u32 = 4;
+const TRACK_DOWNLOAD_BACKOFF_SECS: u64 = 5;
+
+// Models mirror the Unsplash JSON (snake_case) so the frontend can keep using the
+// field names from the public API documentation.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Photo {
+    pub id: String,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub alt_description: Option<String>,
+    pub urls: PhotoUrls,
+    pub user: User,
+    pub links: PhotoLinks,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PhotoUrls {
+    pub raw: String,
+    pub full: String,
+    pub regular: String,
+    pub small: String,
+    pub thumb: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PhotoLinks {
+    #[serde(default)]
+    pub html: Option<String>,
+    pub download_location: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct User {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    pub username: String,
+    pub links: UserLinks,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UserLinks {
+    pub html: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Topic {
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub total_photos: u64,
+    #[serde(default)]
+    pub cover_photo: Option<Photo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Collection {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub total_photos: u64,
+    #[serde(default)]
+    pub cover_photo: Option<Photo>,
+    #[serde(default)]
+    pub user: Option<User>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SearchResults {
+    pub total: u64,
+    pub total_pages: u32,
+    pub results: Vec<Photo>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    pub limit: Option<u32>,
+    pub remaining: Option<u32>,
+    // Unix seconds of the response the values were read from.
+    pub updated_at: Option<u64>,
+}
+
+impl RateLimit {
+    fn update(&mut self, headers: &HeaderMap) {
+        let read = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u32>().ok())
+        };
+        let (limit, remaining) = (read("x-ratelimit-limit"), read("x-ratelimit-remaining"));
+        if limit.is_none() && remaining.is_none() {
+            return;
+        }
+        self.limit = limit.or(self.limit);
+        self.remaining = remaining;
+        self.updated_at = Some(now_secs());
+    }
+
+    // (limit, remaining) if the last reading is recent enough to trust.
+    fn current(&self) -> Option<(u32, u32)> {
+        let updated_at = self.updated_at?;
+        if now_secs().saturating_sub(updated_at) > QUOTA_STALE_SECS {
+            return None;
+        }
+        Some((self.limit?, self.remaining?))
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.current()
+            .is_some_and(|(limit, remaining)| remaining <= limit / LOW_QUOTA_DIVISOR)
+    }
+
+    fn allows(&self, priority: Priority) -> bool {
+        let Some((limit, remaining)) = self.current() else {
+            return true;
+        };
+        match priority {
+            Priority::Interactive => true,
+            Priority::Pagination => !self.is_low(),
+            Priority::Scheduled => remaining > (limit / 10).max(MIN_SCHEDULED_RESERVE),
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Priority {
+    Interactive,
+    Pagination,
+    Scheduled,
+}
+
+pub struct UnsplashClient {
+    http: Client,
+    api_key: String,
+    base_url: String,
+    quota: Arc<Mutex<RateLimit>>,
+    scheduled: bool,
+}
+
+impl UnsplashClient {
+    // WALLCRAFT_UNSPLASH_API_URL points the client at a local mock server.
+    pub fn from_settings(
+        settings: &Settings,
+        quota: Arc<Mutex<RateLimit>>,
+    ) -> Result<Self, String> {
+        if settings.api_key.is_empty() {
+            return Err("Unsplash API key is not configured".to_string());
+        }
+        crate::secrets::ensure_resolved(&settings.api_key)?;
+        Ok(UnsplashClient {
+            http: crate::http::client(settings)?,
+            api_key: settings.api_key.clone(),
+            base_url: std::env::var("WALLCRAFT_UNSPLASH_API_URL")
+                .unwrap_or_else(|_| BASE_URL.to_string()),
+            quota,
+            scheduled: false,
+        })
+    }
+
+    pub fn for_app(app: &AppHandle) -> Result<Self, String> {
+        let quota = app.state::<crate::AppState>().unsplash_quota.clone();
+        Self::from_settings(&crate::settings::load_settings_inner(app), quota)
+    }
+
+    // Marks requests as coming from scheduled rotation, which must leave a reserve.
+    pub fn scheduled(mut self) -> Self {
+        self.scheduled = true;
+        self
+    }
+
+    pub fn quota_is_low(&self) -> bool {
+        self.quota.lock().unwrap().is_low()
+    }
+
+    fn check_budget(&self, page: u32) -> Result<(), String> {
+        let priority = if self.scheduled {
+            Priority::Scheduled
+        } else if page > 1 {
+            Priority::Pagination
+        } else {
+            Priority::Interactive
+        };
+        let quota = self.quota.lock().unwrap();
+        if quota.allows(priority) {
+            return Ok(());
+        }
+        Err(format!(
+            "Unsplash quota is low ({} of {} requests left this hour)",
+            quota.remaining.unwrap_or_default(),
+            quota.limit.unwrap_or_default()
+        ))
+    }
+
+    fn request(&self, url: &str) -> reqwest::RequestBuilder {
+        self.http
+            .get(url)
+            .header("Authorization", format!("Client-ID {}", self.api_key))
+            .header("Accept-Version", "v1")
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, String> {
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        self.quota.lock().unwrap().update(response.headers());
+        Ok(response)
+    }
+
+    // Each segment is percent-encoded, so a slug or id containing `/`, `?` or
+    // `#` cannot reach a different endpoint.
+    fn url(&self, segments: &[&str]) -> Result<Url, String> {
+        if let Some(bad) = segments
+            .iter()
+            .find(|s| s.is_empty() || **s == "." || **s == "..")
+        {
+            return Err(format!("Invalid Unsplash path segment {bad:?}"));
+        }
+        let mut url =
+            Url::parse(&self.base_url).map_err(|e| format!("Invalid Unsplash API URL: {e}"))?;
+        url.path_segments_mut()
+            .map_err(|_| "Invalid Unsplash API URL".to_string())?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        segments: &[&str],
+        query: &[(&str, String)],
+    ) -> Result<T, String> {
+        let page = query
+            .iter()
+            .find(|(key, _)| *key == "page")
+            .and_then(|(_, value)| value.parse().ok())
+            .unwrap_or(1);
+        self.check_budget(page)?;
+
+        let url = self.url(segments)?;
+        let response = self.send(self.request(url.as_str()).query(query)).await?;
+        let status = response.status();
+        if !status.is_success() {
+            let exhausted = self.quota.lock().unwrap().remaining == Some(0);
+            return Err(match status {
+                StatusCode::UNAUTHORIZED => "Unsplash rejected the API key".to_string(),
+                StatusCode::FORBIDDEN if exhausted => {
+                    "Unsplash rate limit reached for this hour".to_string()
+                }
+                _ => format!("Unsplash request {} failed: HTTP {status}", url.path()),
+            });
+        }
+        response
+            .json::<T>()
+            .await
+            .map_err(|e| format!("Unexpected Unsplash response from {}: {e}", url.path()))
+    }
+
+    pub async fn topics(&self) -> Result<Vec<Topic>, String> {
+        self.get(
+            &["topics"],
+            &[
+                ("per_page", "20".to_string()),
+                ("order_by", "featured".to_string()),
+            ],
+        )
+        .await
+    }
+
+    // The editorial feed, used when no topic or search query is configured.
+    pub async fn photos(&self, page: u32) -> Result<Vec<Photo>, String> {
+        self.get(&["photos"], &page_query(page)).await
+    }
+
+    pub async fn topic_photos(&self, slug: &str, page: u32) -> Result<Vec<Photo>, String> {
+        self.get(&["topics", slug, "photos"], &page_query(page))
+            .await
+    }
+
+    pub async fn search_photos(&self, query: &str, page: u32) -> Result<SearchResults, String> {
+        let mut params = page_query(page);
+        params.push(("query", query.to_string()));
+        params.push(("orientation", "landscape".to_string()));
+        self.get(&["search", "photos"], &params).await
+    }
+
+    pub async fn user_photos(&self, username: &str, page: u32) -> Result<Vec<Photo>, String> {
+        self.get(&["users", username, "photos"], &page_query(page))
+            .await
+    }
+
+    pub async fn random_photo(
+        &self,
+        orientation: Option<&str>,
+        content_filter: Option<&str>,
+        query: Option<&str>,
+    ) -> Result<Photo, String> {
+        let params: Vec<(&str, String)> = [
+            ("orientation", orientation),
+            ("content_filter", content_filter),
+            ("query", query),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| {
+            value
+                .filter(|v| !v.trim().is_empty())
+                .map(|v| (key, v.trim().to_string()))
+        })
+        .collect();
+        self.get(&["photos", "random"], &params).await
+    }
+
+    pub async fn collections(&self, page: u32) -> Result<Vec<Collection>, String> {
+        self.get(&["collections"], &page_query(page)).await
+    }
+
+    pub async fn collection_photos(&self, id: &str, page: u32) -> Result<Vec<Photo>, String> {
+        self.get(&["collections", id, "photos"], &page_query(page))
+            .await
+    }
+
+    // One cheap request to tell whether the key is accepted.
+    pub async fn verify_key(&self) -> Result<(), String> {
+        self.get::<Vec<Topic>>(&["topics"], &[("per_page", "1".to_string())])
+            .await
+            .map(|_| ())
+    }
+
+    // Required by the Unsplash API terms whenever a photo is used as a wallpaper.
+    pub async fn track_download(&self, download_location: &str) -> Result<(), String> {
+        let response = self.send(self.request(download_location)).await?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Download tracking failed: HTTP {}",
+                response.status()
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn page_query(page: u32) -> Vec<(&'static str, String)> {
+    vec![
+        ("page", page.max(1).to_string()),
+        ("per_page", PER_PAGE.to_string()),
+    ]
+}
+
+// Waits before each retry of download tracking: 5s, 10s, 20s.
+fn track_download_delays() -> impl Iterator<Item = Duration> {
+    (0..TRACK_DOWNLOAD_ATTEMPTS - 1).map(|i| Duration::from_secs(TRACK_DOWNLOAD_BACKOFF_SECS << i))
+}
+
+// Runs `attempt` until it succeeds, sleeping for each of `delays` in between.
+// The last error is returned once the delays run out.
+async fn with_retries<F, Fut>(
+    delays: impl Iterator<Item = Duration>,
+    mut attempt: F,
+) -> Result<(), String>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let mut delays = delays;
+    loop {
+        match attempt().await {
+            Ok(()) => return Ok(()),
+            Err(e) => match delays.next() {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return Err(e),
+            },
+        }
+    }
+}
+
+// Fires the download_location ping the Unsplash API terms require whenever a photo
+// is applied. Runs detached with exponential backoff so a flaky network never
+// delays or fails the wallpaper change itself.
+pub fn track_download_in_background(app: &AppHandle, download_location: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let (app, download_location) = (&app, &download_location);
+        let result = with_retries(track_download_delays(), || async move {
+            UnsplashClient::for_app(app)?
+                .track_download(download_location)
+                .await
+        })
+        .await;
+        if let Err(e) = result {
+            eprintln!("[WallCraft] giving up on download tracking: {e}");
+        }
+    });
+}
+
+pub fn full_res_url(photo: &Photo) -> String {
+    format!("{}&q=90&w={}", photo.urls.raw, photo.width.max(3840))
+}
+
+#[tauri::command]
+pub fn get_unsplash_rate_limit(state: tauri::State<'_, crate::AppState>) -> RateLimit {
+    state.unsplash_quota.lock().unwrap().clone()
+}
+
+/// Checks an access key before it is saved. Goes through the Rust client so
+/// proxy, certificate and quota handling apply as for every other request.
+#[tauri::command]
+pub async fn unsplash_verify_key(app: AppHandle, api_key: String) -> Result<(), String> {
+    let settings = Settings {
+        api_key: api_key.trim().to_string(),
+        ..crate::settings::load_settings_inner(&app)
+    };
+    let quota = app.state::<crate::AppState>().unsplash_quota.clone();
+    UnsplashClient::from_settings(&settings, quota)?
+        .verify_key()
+        .await
+}
+
+#[tauri::command]
+pub async fn unsplash_topics(app: AppHandle) -> Result<Vec<Topic>, String> {
+    UnsplashClient::for_app(&app)?.topics().await
+}
+
+#[tauri::command]
+pub async fn unsplash_topic_photos(
+    app: AppHandle,
+    slug: String,
+    page: u32,
+) -> Result<Vec<Photo>, String> {
+    UnsplashClient::for_app(&app)?
+        .topic_photos(&slug, page)
+        .await
+}
+
+#[tauri::command]
+pub async fn unsplash_search_photos(
+    app: AppHandle,
+    query: String,
+    page: u32,
+) -> Result<SearchResults, String> {
+    UnsplashClient::for_app(&app)?
+        .search_photos(&query, page)
+        .await
+}
+
+#[tauri::command]
+pub async fn unsplash_collections(app: AppHandle, page: u32) -> Result<Vec<Collection>, String> {
+    UnsplashClient::for_app(&app)?.collections(page).await
+}
+
+#[tauri::command]
+pub async fn unsplash_collection_photos(
+    app: AppHandle,
+    id: String,
+    page: u32,
+) -> Result<Vec<Photo>, String> {
+    UnsplashClient::for_app(&app)?
+        .collection_photos(&id, page)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::test_server::{self, Response, Server};
+    use reqwest::header::{HeaderName, HeaderValue};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    };
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/unsplash/",
+                $name
+            ))
+        };
+    }
+
+    const INVALID_KEY: &str = "invalid-key";
+    static FLAKY_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    // Answers like api.unsplash.com for the endpoints the client uses.
+    fn respond(head: &str) -> Response {
+        if head.contains(&format!("Client-ID {INVALID_KEY}")) {
+            return Response::json(401, fixture!("error_401.json"));
+        }
+        let photo = fixture!("photo.json");
+        let path = head.split_whitespace().nth(1).unwrap_or_default();
+        let path = path.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+        match segments.as_slice() {
+            ["topics"] => Response::json(200, &format!("[{}]", fixture!("topic.json"))),
+            ["collections"] => Response::json(200, &format!("[{}]", fixture!("collection.json"))),
+            ["search", "photos"] => Response::json(
+                200,
+                &fixture!("search.json").replace("[]", &format!("[{photo}]")),
+            ),
+            ["photos", "random"] => Response::json(200, photo),
+            ["photos"]
+            | ["topics", _, "photos"]
+            | ["users", _, "photos"]
+            | ["collections", _, "photos"] => Response::json(200, &format!("[{photo}]")),
+            ["malformed"] => Response::json(200, r#"{"id": 1}"#),
+            ["quota", remaining] => Response::json(200, "[]")
+                .header("X-Ratelimit-Limit", "50")
+                .header("X-Ratelimit-Remaining", remaining),
+            // Fails twice, then succeeds.
+            ["photos", "flaky", "download"] => {
+                if FLAKY_CALLS.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Response::json(503, "{}")
+                } else {
+                    Response::json(200, r#"{"url":"https://images.unsplash.com/x"}"#)
+                }
+            }
+            ["photos", _, "download"] => {
+                Response::json(200, r#"{"url":"https://images.unsplash.com/x"}"#)
+            }
+            ["down", "download"] => Response::json(500, "{}"),
+            _ => Response::json(404, r#"{"errors":["Not found"]}"#),
+        }
+    }
+
+    // One server for every test, reached through WALLCRAFT_UNSPLASH_API_URL
+    // like a developer's local mock would be.
+    fn mock_api() -> &'static Server {
+        static SERVER: OnceLock<Server> = OnceLock::new();
+        SERVER.get_or_init(|| {
+            let server = test_server::start_shared(respond);
+            std::env::set_var("WALLCRAFT_UNSPLASH_API_URL", &server.url);
+            server
+        })
+    }
+
+    fn client(api_key: &str) -> UnsplashClient {
+        mock_api();
+        let settings = Settings {
+            api_key: api_key.to_string(),
+            ..Settings::default()
+        };
+        UnsplashClient::from_settings(&settings, Arc::default()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn parses_typed_models() {
+        let client = client("valid-key");
+
+        let topics = client.topics().await.unwrap();
+        assert_eq!(topics[0].slug, "wallpapers");
+        assert_eq!(topics[0].total_photos, 12354);
+        assert!(topics[0].cover_photo.is_none());
+
+        let photos = client.topic_photos("wallpapers", 1).await.unwrap();
+        let photo = &photos[0];
+        assert_eq!(photo.id, "Dwu85P9SOIk");
+        assert_eq!((photo.width, photo.height), (2448, 3264));
+        assert_eq!(photo.user.username, "exampleuser");
+        assert_eq!(
+            photo.links.download_location,
+            "https://api.unsplash.com/photos/Dwu85P9SOIk/download"
+        );
+
+        let results = client.search_photos("mountains", 2).await.unwrap();
+        assert_eq!((results.total, results.total_pages), (133, 7));
+        assert_eq!(results.results[0].id, "Dwu85P9SOIk");
+
+        let collections = client.collections(1).await.unwrap();
+        assert_eq!(collections[0].id, "206");
+        assert!(collections[0].description.is_none());
+
+        let random = client
+            .random_photo(Some("landscape"), None, Some(" "))
+            .await
+            .unwrap();
+        assert_eq!(random.color.as_deref(), Some("#6E633A"));
+        assert!(client.verify_key().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn sends_the_key_and_version() {
+        client("header-key").photos(3).await.unwrap();
+        let head = mock_api()
+            .requests()
+            .into_iter()
+            .find(|head| head.contains("Client-ID header-key"))
+            .unwrap();
+        assert!(
+            head.starts_with("GET /photos?page=3&per_page=20 "),
+            "{head}"
+        );
+        assert!(head.to_ascii_lowercase().contains("accept-version: v1"));
+    }
+
+    #[tokio::test]
+    async fn encodes_path_segments() {
+        let client = client("encoding-key");
+        client
+            .topic_photos("nature/../../photos?x=1#y", 1)
+            .await
+            .unwrap();
+        client.user_photos("a b", 1).await.unwrap();
+        client.collection_photos("1%2F2", 1).await.unwrap();
+        let lines: Vec<String> = mock_api()
+            .requests()
+            .iter()
+            .filter(|head| head.contains("Client-ID encoding-key"))
+            .map(|head| head.lines().next().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "GET /topics/nature%2F..%2F..%2Fphotos%3Fx=1%23y/photos?page=1&per_page=20 HTTP/1.1",
+                "GET /users/a%20b/photos?page=1&per_page=20 HTTP/1.1",
+                "GET /collections/1%252F2/photos?page=1&per_page=20 HTTP/1.1",
+            ]
+        );
+
+        for bad in ["", ".", ".."] {
+            let error = client.topic_photos(bad, 1).await.unwrap_err();
+            assert!(
+                error.starts_with("Invalid Unsplash path segment"),
+                "{error}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn maps_errors() {
+        let error = client(INVALID_KEY).topics().await.unwrap_err();
+        assert_eq!(error, "Unsplash rejected the API key");
+        assert!(client(INVALID_KEY).verify_key().await.is_err());
+
+        let client = client("error-key");
+        let error = client.get::<Photo>(&["missing"], &[]).await.unwrap_err();
+        assert_eq!(
+            error,
+            "Unsplash request /missing failed: HTTP 404 Not Found"
+        );
+
+        let error = client.get::<Photo>(&["malformed"], &[]).await.unwrap_err();
+        assert!(
+            error.starts_with("Unexpected Unsplash response from /malformed"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn requires_a_key() {
+        let error = UnsplashClient::from_settings(&Settings::default(), Arc::default());
+        assert_eq!(error.err().unwrap(), "Unsplash API key is not configured");
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| {
+                (
+                    HeaderName::from_static(name),
+                    HeaderValue::from_static(value),
+                )
+            })
+            .collect()
+    }
+
+    fn quota(limit: u32, remaining: u32) -> RateLimit {
+        RateLimit {
+            limit: Some(limit),
+            remaining: Some(remaining),
+            updated_at: Some(now_secs()),
+        }
+    }
+
+    #[test]
+    fn reads_rate_limit_headers() {
+        let mut quota = RateLimit::default();
+        quota.update(&headers(&[("content-type", "application/json")]));
+        assert!(quota.updated_at.is_none());
+
+        quota.update(&headers(&[
+            ("x-ratelimit-limit", "50"),
+            ("x-ratelimit-remaining", " 42 "),
+        ]));
+        assert_eq!(quota.current(), Some((50, 42)));
+
+        // The limit is kept when a response only reports what is left.
+        quota.update(&headers(&[("x-ratelimit-remaining", "7")]));
+        assert_eq!(quota.current(), Some((50, 7)));
+
+        quota.update(&headers(&[("x-ratelimit-remaining", "lots")]));
+        assert_eq!((quota.limit, quota.remaining), (Some(50), Some(7)));
+    }
+
+    #[test]
+    fn ignores_stale_readings() {
+        let mut quota = quota(50, 0);
+        quota.updated_at = Some(now_secs() - QUOTA_STALE_SECS - 1);
+        assert_eq!(quota.current(), None);
+        assert!(!quota.is_low());
+        assert!(quota.allows(Priority::Scheduled));
+    }
+
+    #[test]
+    fn scheduled_rotation_leaves_a_reserve() {
+        // A tenth of the limit, but never fewer than five requests.
+        assert!(quota(50, 6).allows(Priority::Scheduled));
+        assert!(!quota(50, 5).allows(Priority::Scheduled));
+        assert!(quota(5000, 501).allows(Priority::Scheduled));
+        assert!(!quota(5000, 500).allows(Priority::Scheduled));
+
+        // Pagination stops at a fifth; the user browsing is never refused.
+        assert!(quota(50, 11).allows(Priority::Pagination));
+        assert!(!quota(50, 10).allows(Priority::Pagination));
+        assert!(quota(50, 0).allows(Priority::Interactive));
+        assert!(RateLimit::default().allows(Priority::Scheduled));
+    }
+
+    #[tokio::test]
+    async fn responses_update_the_shared_quota() {
+        let client = client("quota-key");
+        client
+            .get::<Vec<Photo>>(&["quota", "30"], &[])
+            .await
+            .unwrap();
+        assert_eq!(client.quota.lock().unwrap().current(), Some((50, 30)));
+        assert!(!client.quota_is_low());
+
+        client
+            .get::<Vec<Photo>>(&["quota", "5"], &[])
+            .await
+            .unwrap();
+        assert!(client.quota_is_low());
+
+        // Page 2 and scheduled requests are refused before reaching the server.
+        let sent = || {
+            mock_api()
+                .requests()
+                .iter()
+                .filter(|head| head.contains("Client-ID quota-key"))
+                .count()
+        };
+        assert_eq!(sent(), 2);
+        let error = client
+            .get::<Vec<Photo>>(&["photos"], &page_query(2))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "Unsplash quota is low (5 of 50 requests left this hour)"
+        );
+        let scheduled = client.scheduled();
+        assert!(scheduled.photos(1).await.is_err());
+        assert_eq!(sent(), 2);
+    }
+
+    #[test]
+    fn download_tracking_backs_off_exponentially() {
+        let delays: Vec<u64> = track_download_delays().map(|d| d.as_secs()).collect();
+        assert_eq!(delays, [5, 10, 20]);
+    }
+
+    #[tokio::test]
+    async fn tracks_downloads_with_retries() {
+        let client = client("tracking-key");
+        let url = |path: &str| format!("{}{path}", mock_api().url);
+        let no_wait = || std::iter::repeat_n(Duration::ZERO, 3);
+
+        client
+            .track_download(&url("/photos/Dwu85P9SOIk/download"))
+            .await
+            .unwrap();
+
+        let flaky = url("/photos/flaky/download");
+        let result = with_retries(no_wait(), || client.track_download(&flaky)).await;
+        assert!(result.is_ok());
+        assert_eq!(FLAKY_CALLS.load(Ordering::SeqCst), 3);
+
+        let down = url("/down/download");
+        let error = with_retries(no_wait(), || client.track_download(&down))
+            .await
+            .unwrap_err();
+        assert!(
+            error.starts_with("Download tracking failed: HTTP 500"),
+            "{error}"
+        );
+
+        let lines: Vec<String> = mock_api()
+            .requests()
+            .iter()
+            .filter(|head| head.contains("Client-ID tracking-key"))
+            .map(|head| head.lines().next().unwrap().to_string())
+            .collect();
+        let count = |path: &str| lines.iter().filter(|l| l.contains(path)).count();
+        assert_eq!(count("/photos/Dwu85P9SOIk/download"), 1);
+        assert_eq!(count("/photos/flaky/download"), 3);
+        // One attempt and a retry for each delay.
+        assert_eq!(count("/down/download"), 4);
+    }
+}
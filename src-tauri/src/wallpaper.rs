@@ -1,9 +1,19 @@
-use image::{codecs::jpeg::JpegEncoder, GenericImageView};
-use serde::Serialize;
-use std::{fs, path::PathBuf};
+use image::{
+    codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, GenericImageView, ImageFormat,
+    ImageReader, Limits, RgbImage,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
 use tauri::{AppHandle, Emitter, Manager};
 
+use crate::dedupe::RecentlyShown;
 use crate::screens::ScreenInfo;
+use crate::settings::{Effects, FitMode};
+use crate::sources::{Candidate, DownloadTarget, WallpaperSource};
 
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -12,6 +22,8 @@ struct WallpaperStatus {
     status: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_kind: Option<&'static str>,
 }
 
 fn wallpaper_dir(app: &AppHandle) -> PathBuf {
@@ -25,21 +37,208 @@ fn emit_status(app: &AppHandle, screen_id: &str, status: &str, error: Option<&st
             screen_id: screen_id.to_string(),
             status: status.to_string(),
             error: error.map(|s| s.to_string()),
+            error_kind: None,
         },
     );
 }
 
-async fn download_image(url: &str) -> Result<Vec<u8>, String> {
-    let client = reqwest::Client::builder()
-        .user_agent("WallCraft/1.0")
-        .build()
-        .map_err(|e| e.to_string())?;
-    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+fn emit_download_error(app: &AppHandle, screen_id: &str, error: &DownloadError) {
+    let _ = app.emit(
+        "wallpaper-status",
+        WallpaperStatus {
+            screen_id: screen_id.to_string(),
+            status: "error".to_string(),
+            error: Some(format!("Download failed: {error}")),
+            error_kind: Some(error.kind()),
+        },
+    );
+}
+
+const SUPPORTED_CONTENT_TYPES: &[&str] = &["image/jpeg", "image/jpg", "image/png", "image/webp"];
+
+/// Upper bounds applied to a downloaded image before it is decoded.
+#[derive(Clone, Copy)]
+pub struct DownloadLimits {
+    pub max_bytes: u64,
+    pub max_pixels: u64,
+}
+
+impl DownloadLimits {
+    pub fn from_settings(settings: &crate::settings::Settings) -> Self {
+        DownloadLimits {
+            max_bytes: settings.max_download_mb.saturating_mul(1024 * 1024),
+            max_pixels: settings.max_image_megapixels.saturating_mul(1_000_000),
+        }
+    }
+
+    // RGBA8 is the widest buffer the decoders produce for our formats, but the
+    // image crate's own 512 MB cap stays in force above that.
+    fn decoder_limits(&self) -> Limits {
+        let mut limits = Limits::default();
+        let needed = self.max_pixels.saturating_mul(4);
+        limits.max_alloc = Some(limits.max_alloc.map_or(needed, |cap| cap.min(needed)));
+        limits
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DownloadError {
+    Network(String),
+    Io(String),
+    Http(u16),
+    TooLarge { limit: u64 },
+    UnsupportedContentType(String),
+    NotAnImage,
+    UnsupportedFormat(String),
+    TooManyPixels { width: u32, height: u32, limit: u64 },
+}
+
+impl DownloadError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DownloadError::Network(_) => "network",
+            DownloadError::Io(_) => "io",
+            DownloadError::Http(_) => "http",
+            DownloadError::TooLarge { .. } => "tooLarge",
+            DownloadError::UnsupportedContentType(_) => "unsupportedContentType",
+            DownloadError::NotAnImage => "notAnImage",
+            DownloadError::UnsupportedFormat(_) => "unsupportedFormat",
+            DownloadError::TooManyPixels { .. } => "tooManyPixels",
+        }
+    }
+}
+
+impl std::fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DownloadError::Network(e) => write!(f, "{e}"),
+            DownloadError::Io(e) => write!(f, "{e}"),
+            DownloadError::Http(code) => write!(f, "HTTP {code}"),
+            DownloadError::TooLarge { limit } => {
+                write!(f, "image exceeds the {} MB download limit", limit / (1024 * 1024))
+            }
+            DownloadError::UnsupportedContentType(ct) => {
+                write!(f, "server returned unsupported content type {ct}")
+            }
+            DownloadError::NotAnImage => write!(f, "response is not a recognised image"),
+            DownloadError::UnsupportedFormat(fmt) => write!(f, "unsupported image format {fmt}"),
+            DownloadError::TooManyPixels {
+                width,
+                height,
+                limit,
+            } => write!(
+                f,
+                "image is {width}x{height}, above the {} megapixel limit",
+                limit / 1_000_000
+            ),
+        }
+    }
+}
+
+async fn download_image(
+    client: &reqwest::Client,
+    url: &str,
+    limits: DownloadLimits,
+) -> Result<Vec<u8>, DownloadError> {
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| DownloadError::Network(e.to_string()))?;
     if !response.status().is_success() {
-        return Err(format!("HTTP {}", response.status()));
+        return Err(DownloadError::Http(response.status().as_u16()));
+    }
+
+    check_content_type(response.headers().get(reqwest::header::CONTENT_TYPE))?;
+    if response
+        .content_length()
+        .is_some_and(|len| len > limits.max_bytes)
+    {
+        return Err(DownloadError::TooLarge {
+            limit: limits.max_bytes,
+        });
+    }
+
+    // Content-Length can be absent or wrong, so enforce the limit while streaming too.
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| DownloadError::Network(e.to_string()))?
+    {
+        if bytes.len() as u64 + chunk.len() as u64 > limits.max_bytes {
+            return Err(DownloadError::TooLarge {
+                limit: limits.max_bytes,
+            });
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    validate_image(&bytes, limits)?;
+    Ok(bytes)
+}
+
+fn read_local_image(path: &Path, limits: DownloadLimits) -> Result<Vec<u8>, DownloadError> {
+    let size = fs::metadata(path)
+        .map_err(|e| DownloadError::Io(e.to_string()))?
+        .len();
+    if size > limits.max_bytes {
+        return Err(DownloadError::TooLarge {
+            limit: limits.max_bytes,
+        });
+    }
+    let bytes = fs::read(path).map_err(|e| DownloadError::Io(e.to_string()))?;
+    validate_image(&bytes, limits)?;
+    Ok(bytes)
+}
+
+fn check_content_type(header: Option<&reqwest::header::HeaderValue>) -> Result<(), DownloadError> {
+    let Some(value) = header.and_then(|v| v.to_str().ok()) else {
+        return Ok(());
+    };
+    let mime = value
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    // Some CDNs serve images as a generic binary stream; the magic bytes decide then.
+    if mime == "application/octet-stream" || SUPPORTED_CONTENT_TYPES.contains(&mime.as_str()) {
+        Ok(())
+    } else {
+        Err(DownloadError::UnsupportedContentType(mime))
+    }
+}
+
+/// Checks the magic bytes and header dimensions without decoding any pixel data.
+fn validate_image(bytes: &[u8], limits: DownloadLimits) -> Result<(), DownloadError> {
+    let format = image::guess_format(bytes).map_err(|_| DownloadError::NotAnImage)?;
+    if !matches!(
+        format,
+        ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP
+    ) {
+        return Err(DownloadError::UnsupportedFormat(format!("{format:?}")));
+    }
+
+    let (width, height) = ImageReader::with_format(Cursor::new(bytes), format)
+        .into_dimensions()
+        .map_err(|_| DownloadError::NotAnImage)?;
+    if width as u64 * height as u64 > limits.max_pixels {
+        return Err(DownloadError::TooManyPixels {
+            width,
+            height,
+            limit: limits.max_pixels,
+        });
     }
-    let bytes = response.bytes().await.map_err(|e| e.to_string())?;
-    Ok(bytes.to_vec())
+    Ok(())
+}
+
+pub fn decode_image(bytes: &[u8], limits: DownloadLimits) -> Result<image::DynamicImage, String> {
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?;
+    reader.limits(limits.decoder_limits());
+    reader.decode().map_err(|e| e.to_string())
 }
 
 #[derive(Clone)]
@@ -50,45 +249,143 @@ struct TotalBounds {
     total_height: u32,
 }
 
+/// The region of the source image used for one screen, in image pixels, and the
+/// size it was scaled to.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Crop {
+    pub screen_id: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub output_width: u32,
+    pub output_height: u32,
+}
+
+/// How images are fitted to the screens and adjusted, from the active profile.
+#[derive(Clone, Copy)]
+pub struct Presentation {
+    pub fit: FitMode,
+    pub effects: Effects,
+}
+
+impl Presentation {
+    pub fn from_settings(settings: &crate::settings::Settings) -> Self {
+        Presentation {
+            fit: settings.fit_mode,
+            effects: settings.effects,
+        }
+    }
+
+    /// Names the result, so images prepared ahead of time are redone when it
+    /// changes.
+    pub fn cache_key(&self) -> String {
+        let Effects {
+            blur,
+            brightness,
+            grayscale,
+        } = self.effects;
+        format!("{:?}_b{blur}_l{brightness}_g{}", self.fit, grayscale as u8).to_lowercase()
+    }
+}
+
+// The part of the image a screen shows, as x, y, width and height in image pixels.
+fn crop_region(
+    fit: FitMode,
+    (img_width, img_height): (u32, u32),
+    screen: &ScreenInfo,
+    total_bounds: &TotalBounds,
+) -> (u32, u32, u32, u32) {
+    match fit {
+        FitMode::Span => {
+            let scale_x = img_width as f64 / total_bounds.total_width as f64;
+            let scale_y = img_height as f64 / total_bounds.total_height as f64;
+            let scale = scale_x.max(scale_y);
+
+            let scaled_total_w = total_bounds.total_width as f64 * scale;
+            let scaled_total_h = total_bounds.total_height as f64 * scale;
+            let offset_x = ((img_width as f64 - scaled_total_w) / 2.0).round() as i64;
+            let offset_y = ((img_height as f64 - scaled_total_h) / 2.0).round() as i64;
+
+            let raw_cx =
+                ((screen.x - total_bounds.min_x) as f64 * scale + offset_x as f64).round() as i64;
+            let raw_cy =
+                ((screen.y - total_bounds.min_y) as f64 * scale + offset_y as f64).round() as i64;
+
+            let crop_x = raw_cx.max(0) as u32;
+            let crop_y = raw_cy.max(0) as u32;
+            let crop_width = ((screen.width as f64 * scale).round() as u32)
+                .min(img_width.saturating_sub(crop_x))
+                .max(1);
+            let crop_height = ((screen.height as f64 * scale).round() as u32)
+                .min(img_height.saturating_sub(crop_y))
+                .max(1);
+            (crop_x, crop_y, crop_width, crop_height)
+        }
+        FitMode::Fill => {
+            let scale = (img_width as f64 / screen.width.max(1) as f64)
+                .min(img_height as f64 / screen.height.max(1) as f64);
+            let width = ((screen.width as f64 * scale).round() as u32).clamp(1, img_width);
+            let height = ((screen.height as f64 * scale).round() as u32).clamp(1, img_height);
+            (
+                (img_width - width) / 2,
+                (img_height - height) / 2,
+                width,
+                height,
+            )
+        }
+        FitMode::Fit | FitMode::Stretch => (0, 0, img_width, img_height),
+    }
+}
+
+// Scales the image to fit inside the given size, centred on black.
+fn letterbox(img: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+    let scaled = img.resize(width, height, FilterType::Lanczos3).to_rgb8();
+    let mut canvas = RgbImage::new(width, height);
+    image::imageops::overlay(
+        &mut canvas,
+        &scaled,
+        ((width - scaled.width()) / 2) as i64,
+        ((height - scaled.height()) / 2) as i64,
+    );
+    DynamicImage::ImageRgb8(canvas)
+}
+
+fn apply_effects(mut img: DynamicImage, effects: Effects, scale_factor: f64) -> DynamicImage {
+    if effects.grayscale {
+        img = img.grayscale();
+    }
+    if effects.brightness != 0 {
+        img = img.brighten(effects.brightness * 255 / 100);
+    }
+    if effects.blur > 0.0 {
+        img = img.fast_blur(effects.blur * scale_factor as f32);
+    }
+    img
+}
+
 fn crop_and_save(
     image_bytes: Vec<u8>,
     screen: ScreenInfo,
     total_bounds: TotalBounds,
     output_path: PathBuf,
-) -> Result<(), String> {
-    let img = image::load_from_memory(&image_bytes).map_err(|e| e.to_string())?;
-    let (img_width, img_height) = img.dimensions();
-
-    let scale_x = img_width as f64 / total_bounds.total_width as f64;
-    let scale_y = img_height as f64 / total_bounds.total_height as f64;
-    let scale = scale_x.max(scale_y);
-
-    let scaled_total_w = total_bounds.total_width as f64 * scale;
-    let scaled_total_h = total_bounds.total_height as f64 * scale;
-    let offset_x = ((img_width as f64 - scaled_total_w) / 2.0).round() as i64;
-    let offset_y = ((img_height as f64 - scaled_total_h) / 2.0).round() as i64;
-
-    let raw_cx = ((screen.x - total_bounds.min_x) as f64 * scale + offset_x as f64).round() as i64;
-    let raw_cy = ((screen.y - total_bounds.min_y) as f64 * scale + offset_y as f64).round() as i64;
-
-    let crop_x = raw_cx.max(0) as u32;
-    let crop_y = raw_cy.max(0) as u32;
-    let crop_width = ((screen.width as f64 * scale).round() as u32)
-        .min(img_width.saturating_sub(crop_x))
-        .max(1);
-    let crop_height = ((screen.height as f64 * scale).round() as u32)
-        .min(img_height.saturating_sub(crop_y))
-        .max(1);
+    limits: DownloadLimits,
+    presentation: Presentation,
+) -> Result<Crop, String> {
+    let img = decode_image(&image_bytes, limits)?;
+    let (crop_x, crop_y, crop_width, crop_height) =
+        crop_region(presentation.fit, img.dimensions(), &screen, &total_bounds);
 
     let out_w = (screen.width as f64 * screen.scale_factor).round() as u32;
     let out_h = (screen.height as f64 * screen.scale_factor).round() as u32;
 
     let cropped = img.crop_imm(crop_x, crop_y, crop_width, crop_height);
-    let resized = cropped.resize_exact(
-        out_w.max(1),
-        out_h.max(1),
-        image::imageops::FilterType::Lanczos3,
-    );
+    let resized = match presentation.fit {
+        FitMode::Fit => letterbox(&cropped, out_w.max(1), out_h.max(1)),
+        _ => cropped.resize_exact(out_w.max(1), out_h.max(1), FilterType::Lanczos3),
+    };
+    let resized = apply_effects(resized, presentation.effects, screen.scale_factor);
 
     let file = fs::File::create(&output_path).map_err(|e| e.to_string())?;
     let encoder = JpegEncoder::new_with_quality(file, 95);
@@ -96,7 +393,15 @@ fn crop_and_save(
         .write_with_encoder(encoder)
         .map_err(|e| e.to_string())?;
 
-    Ok(())
+    Ok(Crop {
+        screen_id: screen.id,
+        x: crop_x,
+        y: crop_y,
+        width: crop_width,
+        height: crop_height,
+        output_width: out_w.max(1),
+        output_height: out_h.max(1),
+    })
 }
 
 // Direct NSWorkspace wallpaper setter — no subprocess, no permissions required.
@@ -108,7 +413,7 @@ fn crop_and_save(
 // screen. Spaces that are not currently active on a given screen are updated via
 // AppleScript (best-effort, silently ignored on failure).
 // Must be called from the main thread (NSScreen requires MainThreadMarker).
-// Called via window.run_on_main_thread() from apply_wallpaper.
+// Called via app.run_on_main_thread() from apply_photo_url.
 #[cfg(target_os = "macos")]
 fn set_wallpaper_direct(screen_images: Vec<Option<String>>) -> Result<(), String> {
     use objc2::msg_send;
@@ -229,7 +534,7 @@ end tell"#
 async fn set_wallpapers_for_all_spaces(
     screen_images: &[Option<String>],
     wdir: &PathBuf,
-    window: &tauri::WebviewWindow,
+    app: &AppHandle,
 ) -> Result<(), String> {
     #[cfg(not(target_os = "macos"))]
     {
@@ -247,11 +552,10 @@ async fn set_wallpapers_for_all_spaces(
         // NSScreen::screens requires MainThreadMarker, so we dispatch to the main thread.
         let images_for_main = screen_images.to_vec();
         let (tx, rx) = tokio::sync::oneshot::channel::<Result<(), String>>();
-        window
-            .run_on_main_thread(move || {
-                let _ = tx.send(set_wallpaper_direct(images_for_main));
-            })
-            .map_err(|e| format!("main thread dispatch failed: {e}"))?;
+        app.run_on_main_thread(move || {
+            let _ = tx.send(set_wallpaper_direct(images_for_main));
+        })
+        .map_err(|e| format!("main thread dispatch failed: {e}"))?;
         rx.await.map_err(|_| "channel closed".to_string())??;
 
         // Step 2: Best-effort update of all spaces via AppleScript.
@@ -267,98 +571,527 @@ async fn set_wallpapers_for_all_spaces(
     }
 }
 
+// Applies a photo picked in the gallery. The frontend only browses Unsplash.
 #[tauri::command]
-pub async fn apply_wallpaper(
-    photo_url: String,
-    app: AppHandle,
-    window: tauri::WebviewWindow,
+pub async fn apply_wallpaper(photo: crate::unsplash::Photo, app: AppHandle) -> Result<bool, String> {
+    let source = crate::sources::UnsplashSource::new(
+        crate::unsplash::UnsplashClient::for_app(&app)?,
+        crate::settings::RotationSource::Editorial,
+    );
+    apply_candidate(&app, &source, &crate::sources::unsplash_candidate(&photo)).await
+}
+
+// Entry point for every wallpaper change, whichever source produced it. Returns
+// whether all screens were updated.
+pub async fn apply_candidate(
+    app: &AppHandle,
+    source: &dyn WallpaperSource,
+    candidate: &Candidate,
 ) -> Result<bool, String> {
-    let wdir = wallpaper_dir(&app);
-    fs::create_dir_all(&wdir).map_err(|e| e.to_string())?;
+    Ok(apply_unless_seen(app, source, candidate, None)
+        .await?
+        .unwrap_or(false))
+}
 
-    let screens = crate::screens::collect_screens(&window);
+// Like `apply_candidate`, but gives up before cropping when the downloaded image
+// looks like one in `recent`. Returns None in that case. Some means the image is
+// on at least one screen; a failed download or apply is an error.
+pub async fn apply_unless_seen(
+    app: &AppHandle,
+    source: &dyn WallpaperSource,
+    candidate: &Candidate,
+    recent: Option<&RecentlyShown>,
+) -> Result<Option<bool>, String> {
+    let target = source.resolve_download(candidate).await?;
+    let screens = detect_screens(app)?;
+    let image_bytes = fetch_image(app, &screens, &target).await?;
 
+    let limits = DownloadLimits::from_settings(&crate::settings::load_settings_inner(app));
+    let hash = tokio::task::spawn_blocking({
+        let bytes = image_bytes.clone();
+        move || crate::dedupe::perceptual_hash(&bytes, limits)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .ok();
+    if let (Some(recent), Some(hash)) = (recent, hash) {
+        if recent.has_similar(hash) {
+            return Ok(None);
+        }
+    }
+
+    let applied = apply_image(app, screens, &image_bytes).await?;
+    if applied.succeeded() == 0 {
+        return Err("The wallpaper could not be set on any screen".to_string());
+    }
+    source.on_applied(app, candidate);
+    crate::history::record(app, candidate, &image_bytes, &applied, hash);
+    Ok(Some(applied.complete()))
+}
+
+// Re-applies an image that is already on disk, e.g. from history. Nothing is
+// recorded, so stepping through history does not grow it.
+pub async fn apply_cached(app: &AppHandle, image_bytes: &[u8]) -> Result<Applied, String> {
+    let screens = detect_screens(app)?;
+    apply_image(app, screens, image_bytes).await
+}
+
+pub struct Applied {
+    pub screens: Vec<ScreenInfo>,
+    /// Crops of the screens that were updated.
+    pub crops: Vec<Crop>,
+}
+
+impl Applied {
+    pub fn succeeded(&self) -> usize {
+        self.crops.len()
+    }
+
+    pub fn complete(&self) -> bool {
+        self.crops.len() == self.screens.len()
+    }
+}
+
+pub fn detect_screens(app: &AppHandle) -> Result<Vec<ScreenInfo>, String> {
+    let screens = crate::screens::collect_screens(app);
     if screens.is_empty() {
         return Err("No screens detected".to_string());
     }
+    Ok(screens)
+}
 
-    // Calculate total virtual desktop bounds
-    let min_x = screens.iter().map(|s| s.x).min().unwrap_or(0);
-    let min_y = screens.iter().map(|s| s.y).min().unwrap_or(0);
-    let max_x = screens.iter().map(|s| s.x + s.width as i32).max().unwrap_or(1920);
-    let max_y = screens.iter().map(|s| s.y + s.height as i32).max().unwrap_or(1080);
-    let total_bounds = TotalBounds {
-        min_x,
-        min_y,
-        total_width: (max_x - min_x) as u32,
-        total_height: (max_y - min_y) as u32,
-    };
-
-    // Step 1: Download
-    for s in &screens {
-        emit_status(&app, &s.id, "downloading", None);
+// Download step of the pipeline. Failures are also reported per screen through
+// `wallpaper-status`.
+async fn fetch_image(
+    app: &AppHandle,
+    screens: &[ScreenInfo],
+    target: &DownloadTarget,
+) -> Result<Vec<u8>, String> {
+    for s in screens {
+        emit_status(app, &s.id, "downloading", None);
     }
 
-    let image_bytes = match download_image(&photo_url).await {
-        Ok(b) => b,
+    let settings = crate::settings::load_settings_inner(app);
+    let limits = DownloadLimits::from_settings(&settings);
+    let downloaded = match target {
+        DownloadTarget::Url(url) => match crate::http::client(&settings) {
+            Ok(client) => download_image(&client, url, limits).await,
+            Err(e) => Err(DownloadError::Network(e)),
+        },
+        DownloadTarget::File(path) => read_local_image(path, limits),
+        DownloadTarget::Bytes(bytes) => Ok(bytes.clone()),
+    };
+    match downloaded {
+        Ok(b) => Ok(b),
         Err(e) => {
-            for s in &screens {
-                emit_status(&app, &s.id, "error", Some(&format!("Download failed: {e}")));
+            for s in screens {
+                emit_download_error(app, &s.id, &e);
             }
-            return Ok(false);
+            Err(format!("Download failed: {e}"))
         }
-    };
+    }
+}
 
-    // Step 2: Crop each screen
+// The crop → apply half of the pipeline. It only needs an AppHandle so the
+// scheduler and tray can run it while the webview is hidden or suspended.
+async fn apply_image(
+    app: &AppHandle,
+    screens: Vec<ScreenInfo>,
+    image_bytes: &[u8],
+) -> Result<Applied, String> {
+    let wdir = wallpaper_dir(app);
+    fs::create_dir_all(&wdir).map_err(|e| e.to_string())?;
+
+    let total_bounds = total_bounds(&screens);
+
+    let settings = crate::settings::load_settings_inner(app);
+    let limits = DownloadLimits::from_settings(&settings);
+    let presentation = Presentation::from_settings(&settings);
+
+    // Step 1: Crop each screen
     let mut cropped_paths: Vec<Option<String>> = Vec::new();
+    let mut crops: Vec<Option<Crop>> = Vec::new();
     for screen in &screens {
-        emit_status(&app, &screen.id, "cropping", None);
+        emit_status(app, &screen.id, "cropping", None);
 
         let output_path = wdir.join(format!("wallpaper_{}.jpg", screen.id));
         let result = tokio::task::spawn_blocking({
-            let bytes = image_bytes.clone();
+            let bytes = image_bytes.to_vec();
             let sc = screen.clone();
             let tb = total_bounds.clone();
             let op = output_path.clone();
-            move || crop_and_save(bytes, sc, tb, op)
+            move || crop_and_save(bytes, sc, tb, op, limits, presentation)
         })
         .await
         .map_err(|e| e.to_string())
         .and_then(|r| r);
 
         match result {
-            Ok(()) => cropped_paths.push(Some(output_path.to_string_lossy().to_string())),
+            Ok(crop) => {
+                cropped_paths.push(Some(output_path.to_string_lossy().to_string()));
+                crops.push(Some(crop));
+            }
             Err(e) => {
-                emit_status(&app, &screen.id, "error", Some(&e));
+                emit_status(app, &screen.id, "error", Some(&e));
                 cropped_paths.push(None);
+                crops.push(None);
             }
         }
     }
 
-    // Step 3: Apply all at once
+    // Step 2: Apply all at once
+    if !set_cropped(app, &screens, &cropped_paths, &wdir).await {
+        return Ok(Applied {
+            screens,
+            crops: Vec::new(),
+        });
+    }
+
+    Ok(Applied {
+        screens,
+        crops: crops.into_iter().flatten().collect(),
+    })
+}
+
+fn total_bounds(screens: &[ScreenInfo]) -> TotalBounds {
+    // Calculate total virtual desktop bounds
+    let min_x = screens.iter().map(|s| s.x).min().unwrap_or(0);
+    let min_y = screens.iter().map(|s| s.y).min().unwrap_or(0);
+    let max_x = screens.iter().map(|s| s.x + s.width as i32).max().unwrap_or(1920);
+    let max_y = screens.iter().map(|s| s.y + s.height as i32).max().unwrap_or(1080);
+    TotalBounds {
+        min_x,
+        min_y,
+        total_width: (max_x - min_x) as u32,
+        total_height: (max_y - min_y) as u32,
+    }
+}
+
+// Hands the per-screen files to the OS and reports the outcome per screen.
+// Returns false when the OS call failed.
+async fn set_cropped(
+    app: &AppHandle,
+    screens: &[ScreenInfo],
+    cropped_paths: &[Option<String>],
+    wdir: &Path,
+) -> bool {
     for (screen, path) in screens.iter().zip(cropped_paths.iter()) {
         if path.is_some() {
-            emit_status(&app, &screen.id, "applying", None);
+            emit_status(app, &screen.id, "applying", None);
         }
     }
 
-    match set_wallpapers_for_all_spaces(&cropped_paths, &wdir, &window).await {
+    match set_wallpapers_for_all_spaces(cropped_paths, &wdir.to_path_buf(), app).await {
         Ok(()) => {
             for (screen, path) in screens.iter().zip(cropped_paths.iter()) {
                 if path.is_some() {
-                    emit_status(&app, &screen.id, "success", None);
+                    emit_status(app, &screen.id, "success", None);
                 }
             }
+            true
         }
         Err(e) => {
             for (screen, path) in screens.iter().zip(cropped_paths.iter()) {
                 if path.is_some() {
-                    emit_status(&app, &screen.id, "error", Some(&e));
+                    emit_status(app, &screen.id, "error", Some(&e));
                 }
             }
-            return Ok(false);
+            false
         }
     }
+}
+
+/// Identifies a monitor arrangement. Crops depend on every screen's position and
+/// size, so pre-cropped files are only valid for the layout they were made for.
+pub fn layout_key(screens: &[ScreenInfo]) -> String {
+    screens
+        .iter()
+        .map(|s| {
+            format!(
+                "{}x{}+{}+{}@{}",
+                s.width, s.height, s.x, s.y, s.scale_factor
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("_")
+        .replace('-', "m")
+        .replace('.', "p")
+}
 
-    Ok(cropped_paths.iter().all(|p| p.is_some()))
+fn precropped_path(dir: &Path, stem: &str, screen: &ScreenInfo) -> PathBuf {
+    dir.join(format!("{stem}_{}.jpg", screen.id))
+}
+
+/// Crops an image for every screen ahead of time, into `dir` as
+/// `{stem}_{screen id}.jpg`. Wallpaper sets prepare all their frames this way
+/// so switching frames later needs no decoding.
+pub async fn precrop(
+    app: &AppHandle,
+    screens: &[ScreenInfo],
+    image_bytes: Vec<u8>,
+    dir: &Path,
+    stem: &str,
+    presentation: Presentation,
+) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let limits = DownloadLimits::from_settings(&crate::settings::load_settings_inner(app));
+    let bounds = total_bounds(screens);
+    for screen in screens {
+        let output_path = precropped_path(dir, stem, screen);
+        tokio::task::spawn_blocking({
+            let bytes = image_bytes.clone();
+            let sc = screen.clone();
+            let tb = bounds.clone();
+            move || crop_and_save(bytes, sc, tb, output_path, limits, presentation)
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+    }
+    Ok(())
+}
+
+/// Puts images prepared by `precrop` on screen. Returns whether every screen
+/// was updated.
+pub async fn apply_precropped(
+    app: &AppHandle,
+    screens: &[ScreenInfo],
+    dir: &Path,
+    stem: &str,
+) -> Result<bool, String> {
+    let paths: Vec<Option<String>> = screens
+        .iter()
+        .map(|screen| {
+            let path = precropped_path(dir, stem, screen);
+            path.exists()
+                .then(|| path.to_string_lossy().to_string())
+        })
+        .collect();
+    for (screen, path) in screens.iter().zip(&paths) {
+        if path.is_none() {
+            emit_status(app, &screen.id, "error", Some("Frame was not prepared for this screen"));
+        }
+    }
+    let wdir = wallpaper_dir(app);
+    fs::create_dir_all(&wdir).map_err(|e| e.to_string())?;
+    Ok(set_cropped(app, screens, &paths, &wdir).await && paths.iter().all(Option::is_some))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::test_server;
+    use image::Rgb;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    const LIMITS: DownloadLimits = DownloadLimits {
+        max_bytes: 1000,
+        max_pixels: 1_000_000,
+    };
+
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for &byte in bytes {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xedb8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    fn png_chunk(png: &mut Vec<u8>, kind: &[u8], data: &[u8]) {
+        let body = [kind, data].concat();
+        png.extend((data.len() as u32).to_be_bytes());
+        png.extend(&body);
+        png.extend(crc32(&body).to_be_bytes());
+    }
+
+    // A PNG header claiming the given size, followed by no pixel data at all.
+    fn png_header(width: u32, height: u32) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        let header = [
+            &width.to_be_bytes()[..],
+            &height.to_be_bytes(),
+            &[8, 2, 0, 0, 0],
+        ]
+        .concat();
+        png_chunk(&mut png, b"IHDR", &header);
+        png_chunk(&mut png, b"IDAT", &[]);
+        png
+    }
+
+    #[test]
+    fn validation_reads_only_the_header() {
+        assert!(validate_image(&png_header(1000, 1000), LIMITS).is_ok());
+        assert!(matches!(
+            validate_image(&png_header(40_000, 30_000), LIMITS),
+            Err(DownloadError::TooManyPixels {
+                width: 40_000,
+                height: 30_000,
+                ..
+            })
+        ));
+        assert!(matches!(
+            validate_image(b"<html>not found</html>", LIMITS),
+            Err(DownloadError::NotAnImage)
+        ));
+        assert!(matches!(
+            validate_image(b"GIF89a\x01\x00\x01\x00", LIMITS),
+            Err(DownloadError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn decoder_allocation_stays_under_the_image_crate_default() {
+        let default = Limits::default().max_alloc.unwrap();
+        let generous = DownloadLimits {
+            max_bytes: 0,
+            max_pixels: 200_000_000,
+        };
+        assert_eq!(generous.decoder_limits().max_alloc, Some(default));
+        assert_eq!(LIMITS.decoder_limits().max_alloc, Some(4_000_000));
+    }
+
+    #[test]
+    fn content_types() {
+        let check = |value: &str| {
+            check_content_type(Some(
+                &reqwest::header::HeaderValue::from_str(value).unwrap(),
+            ))
+        };
+        assert!(check("image/jpeg").is_ok());
+        assert!(check("Image/PNG; charset=binary").is_ok());
+        assert!(check("application/octet-stream").is_ok());
+        assert!(check_content_type(None).is_ok());
+        assert!(matches!(
+            check("text/html; charset=utf-8"),
+            Err(DownloadError::UnsupportedContentType(mime)) if mime == "text/html"
+        ));
+        assert!(matches!(
+            check("image/gif"),
+            Err(DownloadError::UnsupportedContentType(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn oversized_downloads_are_cut_off() {
+        let client = reqwest::Client::new();
+
+        // Announced by Content-Length.
+        let server = test_server::start(|_| test_server::Response {
+            status: 200,
+            headers: vec![("Content-Type".into(), "image/png".into())],
+            body: "x".repeat(2000),
+        })
+        .await;
+        let result = download_image(&client, &server.url, LIMITS).await;
+        assert!(matches!(
+            result,
+            Err(DownloadError::TooLarge { limit: 1000 })
+        ));
+
+        // Unannounced, so only counting the streamed bytes catches it.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut head = [0u8; 1024];
+            let _ = stream.read(&mut head).await;
+            let _ = stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nConnection: close\r\n\r\n",
+                )
+                .await;
+            for _ in 0..4 {
+                let _ = stream.write_all(&[0u8; 500]).await;
+            }
+        });
+        let result = download_image(&client, &url, LIMITS).await;
+        assert!(matches!(
+            result,
+            Err(DownloadError::TooLarge { limit: 1000 })
+        ));
+    }
+
+    fn screen(x: i32, width: u32, height: u32) -> ScreenInfo {
+        ScreenInfo {
+            id: format!("screen{x}"),
+            name: String::new(),
+            x,
+            y: 0,
+            width,
+            height,
+            scale_factor: 1.0,
+        }
+    }
+
+    #[test]
+    fn crop_regions_follow_the_fit_mode() {
+        let screens = [screen(0, 1920, 1080), screen(1920, 1920, 1080)];
+        let bounds = total_bounds(&screens);
+        let image = (4000, 1000);
+        let region = |fit, screen| crop_region(fit, image, screen, &bounds);
+
+        // Spanning gives each screen its own half of the image.
+        assert_eq!(region(FitMode::Span, &screens[0]), (0, 0, 2000, 1000));
+        assert_eq!(region(FitMode::Span, &screens[1]), (2000, 0, 2000, 1000));
+        // The other modes give every screen the same part.
+        for screen in &screens {
+            assert_eq!(region(FitMode::Fill, screen), (1111, 0, 1778, 1000));
+            assert_eq!(region(FitMode::Fit, screen), (0, 0, 4000, 1000));
+            assert_eq!(region(FitMode::Stretch, screen), (0, 0, 4000, 1000));
+        }
+    }
+
+    #[test]
+    fn fit_centres_the_image_on_black() {
+        let white = DynamicImage::ImageRgb8(RgbImage::from_pixel(40, 10, Rgb([255; 3])));
+        let boxed = letterbox(&white, 40, 40).to_rgb8();
+        assert_eq!(boxed.dimensions(), (40, 40));
+        assert_eq!(boxed.get_pixel(20, 2), &Rgb([0; 3]));
+        assert_eq!(boxed.get_pixel(20, 20), &Rgb([255; 3]));
+        assert_eq!(boxed.get_pixel(20, 37), &Rgb([0; 3]));
+    }
+
+    #[test]
+    fn effects_darken_and_desaturate() {
+        let red = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, Rgb([200, 0, 0])));
+        let same = apply_effects(red.clone(), Effects::default(), 1.0);
+        assert_eq!(same.to_rgb8().get_pixel(0, 0), &Rgb([200, 0, 0]));
+
+        let effects = Effects {
+            brightness: -10,
+            grayscale: true,
+            ..Effects::default()
+        };
+        let pixel = *apply_effects(red, effects, 1.0).to_rgb8().get_pixel(0, 0);
+        assert!(pixel[0] == pixel[1] && pixel[1] == pixel[2]);
+        // Rec. 709 luma of the red, 42, less a tenth of full brightness.
+        assert_eq!(pixel[0], 42 - 25);
+    }
+
+    #[test]
+    fn cache_keys_differ_by_presentation() {
+        let plain = Presentation {
+            fit: FitMode::Span,
+            effects: Effects::default(),
+        };
+        let blurred = Presentation {
+            effects: Effects {
+                blur: 2.5,
+                ..Effects::default()
+            },
+            ..plain
+        };
+        assert_eq!(plain.cache_key(), "span_b0_l0_g0");
+        assert_eq!(blurred.cache_key(), "span_b2.5_l0_g0");
+        assert!(crate::settings::is_plain_file_name(&blurred.cache_key()));
+    }
 }
@@ -8,8 +8,16 @@ import Settings from './components/Settings'
 import PreviewModal from './components/PreviewModal'
 import Toast from './components/Toast'
 import ApiKeySetup from './components/ApiKeySetup'
+import SecretStoreUnlock from './components/SecretStoreUnlock'
 import { fetchTopics } from './services/unsplash'
-import { ScreenInfo, Settings as SettingsType, WallpaperStatus } from './types'
+import {
+  ScreenInfo,
+  SecretStoreStatus,
+  Settings as SettingsType,
+  SettingsChanged,
+  SettingsError,
+  WallpaperStatus
+} from './types'
 
 export default function App() {
   const {
@@ -19,12 +27,23 @@ export default function App() {
     setScreens,
     setTopics,
     addToast,
-    setWallpaperStatus
+    setWallpaperStatus,
+    secretStore,
+    setSecretStore
   } = useStore()
 
   useEffect(() => {
     let unsubStatus: (() => void) | undefined
     let unsubNext: (() => void) | undefined
+    let unsubSchedulerError: (() => void) | undefined
+    let unsubSettingsChanged: (() => void) | undefined
+    let unsubSettingsInvalid: (() => void) | undefined
+    let unsubSecretStoreError: (() => void) | undefined
+
+    const refreshSecretStore = () =>
+      invoke<SecretStoreStatus>('get_secret_store_status')
+        .then(setSecretStore)
+        .catch((error) => console.error('Secret store status error:', error))
 
     const init = async () => {
       try {
@@ -36,6 +55,7 @@ export default function App() {
       } catch (error) {
         console.error('Init error:', error)
       }
+      refreshSecretStore()
 
       unsubStatus = await listen<WallpaperStatus>('wallpaper-status', (event) => {
         const status = event.payload
@@ -50,6 +70,28 @@ export default function App() {
       unsubNext = await listen('next-wallpaper', () => {
         addToast('Switching to next wallpaper...', 'info')
       })
+
+      unsubSchedulerError = await listen<string>('scheduler-error', (event) => {
+        addToast(`Rotation failed: ${event.payload}`, 'error')
+      })
+
+      unsubSettingsChanged = await listen<SettingsChanged>('settings-changed', (event) => {
+        setSettings(event.payload.settings)
+      })
+
+      unsubSettingsInvalid = await listen<SettingsError>('settings-invalid', (event) => {
+        const error = event.payload
+        const detail =
+          error.kind === 'invalid'
+            ? error.errors.map((e) => `${e.field}: ${e.message}`).join('; ')
+            : error.message
+        addToast(`settings.json was not applied: ${detail}`, 'error')
+      })
+
+      unsubSecretStoreError = await listen<string>('secret-store-error', (event) => {
+        addToast(`API keys unavailable: ${event.payload}`, 'error')
+        refreshSecretStore()
+      })
     }
 
     init()
@@ -57,17 +99,39 @@ export default function App() {
     return () => {
       unsubStatus?.()
       unsubNext?.()
+      unsubSchedulerError?.()
+      unsubSettingsChanged?.()
+      unsubSettingsInvalid?.()
+      unsubSecretStoreError?.()
     }
   }, [])
 
   useEffect(() => {
     if (settings.apiKey) {
-      fetchTopics(settings.apiKey)
+      fetchTopics()
         .then(setTopics)
         .catch(() => addToast('Failed to load topics', 'error'))
     }
   }, [settings.apiKey])
 
+  // Keys stay unreadable until the passphrase store is unlocked, so ask first.
+  if (secretStore?.locked) {
+    return (
+      <div className="flex h-screen w-screen items-center justify-center bg-gray-50">
+        <div className="w-full max-w-md bg-white rounded-2xl shadow-macos-lg p-6 space-y-4">
+          <div>
+            <h2 className="text-lg font-semibold text-gray-900 mb-1">Unlock WallCraft</h2>
+            <p className="text-sm text-gray-500">
+              Your API keys are kept in a passphrase-protected store.
+            </p>
+          </div>
+          <SecretStoreUnlock />
+        </div>
+        <Toast />
+      </div>
+    )
+  }
+
   if (!settings.apiKey) {
     return (
       <div className="flex h-screen w-screen items-center justify-center bg-gray-50">
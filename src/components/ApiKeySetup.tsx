@@ -2,6 +2,7 @@ import React, { useState } from 'react'
 import { invoke } from '@tauri-apps/api/core'
 import { useStore } from '../store/useStore'
 import { Settings } from '../types'
+import { verifyApiKey } from '../services/unsplash'
 
 export default function ApiKeySetup() {
   const { setSettings, addToast } = useStore()
@@ -18,19 +19,19 @@ export default function ApiKeySetup() {
 
     setIsSaving(true)
     try {
-      const response = await fetch('https://api.unsplash.com/topics?per_page=1', {
-        headers: { Authorization: `Client-ID ${key}` }
-      })
-      if (!response.ok) {
-        throw new Error('Invalid API key')
-      }
+      await verifyApiKey(key)
 
-      const settings: Settings = { apiKey: key, topicSlug: '', updateInterval: 'manual' }
+      // Only the key changes; sources, profiles, proxy and limits are kept.
+      const current = await invoke<Settings>('get_settings')
+      const settings: Settings = { ...current, apiKey: key }
       await invoke('save_settings', { settings })
       setSettings(settings)
       addToast('API key saved successfully', 'success')
-    } catch {
-      addToast('Invalid API key. Please check and try again.', 'error')
+    } catch (error) {
+      addToast(
+        typeof error === 'string' ? error : 'Invalid API key. Please check and try again.',
+        'error'
+      )
     } finally {
       setIsSaving(false)
     }
@@ -34,13 +34,13 @@ export default function Gallery() {
       try {
         let newPhotos
         if (searchQuery) {
-          const result = await searchPhotos(settings.apiKey, searchQuery, page)
+          const result = await searchPhotos(searchQuery, page)
           newPhotos = result.results
           if (page >= result.total_pages) {
             setHasMorePhotos(false)
           }
         } else {
-          newPhotos = await fetchTopicPhotos(settings.apiKey, selectedTopic, page)
+          newPhotos = await fetchTopicPhotos(selectedTopic, page)
           if (newPhotos.length === 0) {
             setHasMorePhotos(false)
           }
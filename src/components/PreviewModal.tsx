@@ -1,7 +1,6 @@
 import React, { useMemo } from 'react'
 import { invoke } from '@tauri-apps/api/core'
 import { useStore } from '../store/useStore'
-import { getFullResUrl, trackDownload } from '../services/unsplash'
 
 export default function PreviewModal() {
   const {
@@ -62,9 +61,7 @@ export default function PreviewModal() {
     clearWallpaperStatuses()
 
     try {
-      await trackDownload(settings.apiKey, previewPhoto.links.download_location)
-      const fullUrl = getFullResUrl(previewPhoto)
-      const success = await invoke<boolean>('apply_wallpaper', { photoUrl: fullUrl })
+      const success = await invoke<boolean>('apply_wallpaper', { photo: previewPhoto })
       if (success) {
         addToast('Wallpaper applied to all screens!', 'success')
       }
@@ -0,0 +1,54 @@
+import React, { useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { useStore } from '../store/useStore'
+import { SecretStoreStatus } from '../types'
+
+// Asks for the passphrase of the encrypted secret store. The first passphrase
+// entered creates the store; later ones must match it.
+export default function SecretStoreUnlock() {
+  const { setSecretStore, addToast } = useStore()
+  const [passphrase, setPassphrase] = useState('')
+  const [isUnlocking, setIsUnlocking] = useState(false)
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault()
+    setIsUnlocking(true)
+    try {
+      await invoke('unlock_secret_store', { passphrase })
+      setSecretStore(await invoke<SecretStoreStatus>('get_secret_store_status'))
+      setPassphrase('')
+      addToast('Secret store unlocked', 'success')
+    } catch (error) {
+      addToast(typeof error === 'string' ? error : 'Failed to unlock the secret store', 'error')
+    } finally {
+      setIsUnlocking(false)
+    }
+  }
+
+  return (
+    <form onSubmit={handleSubmit} className="space-y-3">
+      <label className="block">
+        <span className="text-sm text-gray-600">Secret store passphrase</span>
+        <input
+          type="password"
+          value={passphrase}
+          onChange={(e) => setPassphrase(e.target.value)}
+          placeholder="Passphrase"
+          className="mt-1 block w-full px-3 py-2 bg-white border border-gray-200 rounded-lg text-sm focus:outline-none focus:ring-2 focus:ring-[#007AFF]/30 focus:border-[#007AFF]/30"
+          autoFocus
+        />
+      </label>
+      <p className="text-xs text-gray-400">
+        API keys are encrypted with this passphrase. If there is no store yet, it is created with
+        the passphrase you enter now.
+      </p>
+      <button
+        type="submit"
+        disabled={isUnlocking || !passphrase}
+        className="w-full px-4 py-2 bg-[#007AFF] hover:bg-[#0056CC] text-white text-sm font-medium rounded-lg transition-colors disabled:opacity-50"
+      >
+        {isUnlocking ? 'Unlocking...' : 'Unlock'}
+      </button>
+    </form>
+  )
+}
@@ -1,7 +1,14 @@
 import React, { useState, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/core'
 import { useStore } from '../store/useStore'
-import { Settings as SettingsType, ScreenInfo } from '../types'
+import {
+  Settings as SettingsType,
+  ScreenInfo,
+  SecretStorage,
+  SecretStoreStatus,
+  SettingsError
+} from '../types'
+import SecretStoreUnlock from './SecretStoreUnlock'
 
 const UPDATE_INTERVALS = [
   { value: 'manual', label: 'Manual' },
@@ -11,8 +18,16 @@ const UPDATE_INTERVALS = [
   { value: 'daily', label: 'Daily' }
 ] as const
 
+const SECRET_STORAGES: { value: SecretStorage; label: string }[] = [
+  { value: 'auto', label: 'Automatic' },
+  { value: 'keyring', label: 'System keychain' },
+  { value: 'machineKeyFile', label: 'File encrypted for this computer' },
+  { value: 'passphraseFile', label: 'File encrypted with a passphrase' }
+]
+
 export default function Settings() {
-  const { settings, setSettings, screens, setScreens, addToast } = useStore()
+  const { settings, setSettings, screens, setScreens, addToast, secretStore, setSecretStore } =
+    useStore()
   const [form, setForm] = useState<SettingsType>(settings)
   const [isSaving, setIsSaving] = useState(false)
   const [isRefreshing, setIsRefreshing] = useState(false)
@@ -26,9 +41,17 @@ export default function Settings() {
     try {
       await invoke('save_settings', { settings: form })
       setSettings(form)
+      setSecretStore(await invoke<SecretStoreStatus>('get_secret_store_status'))
       addToast('Settings saved', 'success')
-    } catch {
-      addToast('Failed to save settings', 'error')
+    } catch (error) {
+      const e = error as SettingsError
+      const message =
+        e?.kind === 'invalid' && e.errors.length > 0
+          ? e.errors[0].message
+          : e?.kind === 'io'
+            ? e.message
+            : 'Failed to save settings'
+      addToast(message, 'error')
     } finally {
       setIsSaving(false)
     }
@@ -88,6 +111,45 @@ export default function Settings() {
           </div>
         </section>
 
+        <section className="space-y-3">
+          <h2 className="text-sm font-semibold text-gray-700 uppercase tracking-wider">
+            Key Storage
+          </h2>
+          <div className="bg-gray-50 rounded-xl p-4 space-y-3">
+            <label className="block">
+              <span className="text-sm text-gray-600">Keep API keys in</span>
+              <select
+                value={form.secretStorage ?? 'auto'}
+                onChange={(e) => setForm({ ...form, secretStorage: e.target.value as SecretStorage })}
+                className="mt-1 block w-full px-3 py-2 bg-white border border-gray-200 rounded-lg text-sm focus:outline-none focus:ring-2 focus:ring-[#007AFF]/30 focus:border-[#007AFF]/30"
+              >
+                {SECRET_STORAGES.map((opt) => (
+                  <option
+                    key={opt.value}
+                    value={opt.value}
+                    disabled={opt.value === 'keyring' && secretStore?.keyringAvailable === false}
+                  >
+                    {opt.label}
+                  </option>
+                ))}
+              </select>
+            </label>
+            {secretStore && (
+              <p className="text-xs text-gray-400">
+                {secretStore.locked
+                  ? 'Locked: enter the passphrase to use your API keys.'
+                  : secretStore.backend
+                    ? `In use: ${SECRET_STORAGES.find((opt) => opt.value === secretStore.backend)?.label}`
+                    : 'No keys stored yet.'}
+                {!secretStore.keyringAvailable && ' The system keychain is not available.'}
+              </p>
+            )}
+            {form.secretStorage === 'passphraseFile' && !secretStore?.unlocked && (
+              <SecretStoreUnlock />
+            )}
+          </div>
+        </section>
+
         <section className="space-y-3">
           <div className="flex items-center justify-between">
             <h2 className="text-sm font-semibold text-gray-700 uppercase tracking-wider">
@@ -1,61 +1,25 @@
+import { invoke } from '@tauri-apps/api/core'
 import { UnsplashPhoto, UnsplashTopic } from '../types'
 
-const BASE_URL = 'https://api.unsplash.com'
-const PER_PAGE = 20
-
-function getHeaders(apiKey: string): HeadersInit {
-  return {
-    Authorization: `Client-ID ${apiKey}`,
-    'Accept-Version': 'v1'
-  }
+// Resolves if Unsplash accepts the key; rejects with the reason otherwise.
+export async function verifyApiKey(apiKey: string): Promise<void> {
+  return invoke('unsplash_verify_key', { apiKey })
 }
 
-export async function fetchTopics(apiKey: string): Promise<UnsplashTopic[]> {
-  const response = await fetch(`${BASE_URL}/topics?per_page=20&order_by=featured`, {
-    headers: getHeaders(apiKey)
-  })
-  if (!response.ok) {
-    throw new Error(`Failed to fetch topics: ${response.status}`)
-  }
-  return response.json()
+export async function fetchTopics(): Promise<UnsplashTopic[]> {
+  return invoke<UnsplashTopic[]>('unsplash_topics')
 }
 
 export async function fetchTopicPhotos(
-  apiKey: string,
   topicSlug: string,
   page: number = 1
 ): Promise<UnsplashPhoto[]> {
-  const response = await fetch(
-    `${BASE_URL}/topics/${topicSlug}/photos?page=${page}&per_page=${PER_PAGE}`,
-    { headers: getHeaders(apiKey) }
-  )
-  if (!response.ok) {
-    throw new Error(`Failed to fetch photos: ${response.status}`)
-  }
-  return response.json()
+  return invoke<UnsplashPhoto[]>('unsplash_topic_photos', { slug: topicSlug, page })
 }
 
 export async function searchPhotos(
-  apiKey: string,
   query: string,
   page: number = 1
 ): Promise<{ results: UnsplashPhoto[]; total_pages: number }> {
-  const response = await fetch(
-    `${BASE_URL}/search/photos?query=${encodeURIComponent(query)}&page=${page}&per_page=${PER_PAGE}&orientation=landscape`,
-    { headers: getHeaders(apiKey) }
-  )
-  if (!response.ok) {
-    throw new Error(`Failed to search photos: ${response.status}`)
-  }
-  return response.json()
-}
-
-export async function trackDownload(apiKey: string, downloadLocation: string): Promise<void> {
-  await fetch(downloadLocation, {
-    headers: getHeaders(apiKey)
-  })
-}
-
-export function getFullResUrl(photo: UnsplashPhoto): string {
-  return `${photo.urls.raw}&q=90&w=${Math.max(photo.width, 3840)}`
+  return invoke('unsplash_search_photos', { query, page })
 }
@@ -1,5 +1,12 @@
 import { create } from 'zustand'
-import { ScreenInfo, Settings, UnsplashPhoto, UnsplashTopic, WallpaperStatus } from '../types'
+import {
+  ScreenInfo,
+  SecretStoreStatus,
+  Settings,
+  UnsplashPhoto,
+  UnsplashTopic,
+  WallpaperStatus
+} from '../types'
 
 type Page = 'gallery' | 'settings'
 
@@ -19,6 +26,9 @@ interface AppState {
   settings: Settings
   setSettings: (settings: Settings) => void
 
+  secretStore: SecretStoreStatus | null
+  setSecretStore: (status: SecretStoreStatus) => void
+
   topics: UnsplashTopic[]
   setTopics: (topics: UnsplashTopic[]) => void
   selectedTopic: string
@@ -61,6 +71,9 @@ export const useStore = create<AppState>((set) => ({
   settings: { apiKey: '', topicSlug: '', updateInterval: 'manual' },
   setSettings: (settings) => set({ settings }),
 
+  secretStore: null,
+  setSecretStore: (status) => set({ secretStore: status }),
+
   topics: [],
   setTopics: (topics) => set({ topics }),
   selectedTopic: '',
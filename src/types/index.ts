@@ -8,10 +8,75 @@ export interface ScreenInfo {
   scaleFactor: number
 }
 
+export type RotationSource =
+  | { type: 'editorial' }
+  | { type: 'topic'; slug: string }
+  | { type: 'collection'; id: string }
+  | { type: 'user'; username: string }
+  | { type: 'search'; query: string }
+  | {
+      type: 'random'
+      orientation?: 'landscape' | 'portrait' | 'squarish' | null
+      contentFilter?: 'low' | 'high' | null
+      query?: string | null
+    }
+
 export interface Settings {
   apiKey: string
   topicSlug: string
+  version?: number
   updateInterval: 'manual' | '30min' | '1hour' | '6hour' | 'daily'
+  rotationSource?: RotationSource | null
+  activeProfile?: string
+  secretStorage?: SecretStorage
+  fitMode?: FitMode
+  effects?: Effects
+}
+
+export type FitMode = 'span' | 'fill' | 'fit' | 'stretch'
+
+export interface Effects {
+  blur: number
+  brightness: number
+  grayscale: boolean
+}
+
+export type SecretStorage = 'auto' | 'keyring' | 'machineKeyFile' | 'passphraseFile'
+
+export interface SecretStoreStatus {
+  storage: SecretStorage
+  backend: SecretStorage | null
+  keyringAvailable: boolean
+  unlocked: boolean
+  locked: boolean
+}
+
+export interface Profile {
+  name: string
+  rotationSource?: RotationSource | null
+  updateInterval: Settings['updateInterval']
+  fitMode?: FitMode
+  effects?: Effects
+}
+
+export interface ProfileList {
+  active: string
+  profiles: Profile[]
+}
+
+export interface FieldError {
+  field: string
+  message: string
+}
+
+export type SettingsError =
+  | { kind: 'invalid'; errors: FieldError[] }
+  | { kind: 'unreadable'; message: string }
+  | { kind: 'io'; message: string }
+
+export interface SettingsChanged {
+  changed: string[]
+  settings: Settings
 }
 
 export interface UnsplashPhoto {
@@ -57,4 +122,5 @@ export interface WallpaperStatus {
   screenId: string
   status: 'pending' | 'downloading' | 'cropping' | 'applying' | 'success' | 'error'
   error?: string
+  errorKind?: string
 }